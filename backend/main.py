@@ -4,10 +4,21 @@ AI Account Management & Protocol Proxy System
 """
 
 import os
+import sys
+import logging
 import multiprocessing
+import threading
 multiprocessing.freeze_support()  # PyInstaller Windows 必须放在最顶部，防止子进程无限递归
 
+__version__ = "0.1.0"
+
+# Tauri 外壳的 --version 会直接运行 sidecar 查询版本，须在导入数据库等模块之前返回
+if __name__ == "__main__" and "--version" in sys.argv[1:]:
+    print(__version__, flush=True)
+    sys.exit(0)
+
 from contextlib import asynccontextmanager
+from urllib.parse import urlsplit
 
 from fastapi import FastAPI
 from fastapi.middleware.cors import CORSMiddleware
@@ -41,6 +52,9 @@ async def lifespan(app: FastAPI):
     monitor_task = asyncio.create_task(start_proxy_monitor())
     refresh_task = asyncio.create_task(start_auto_refresh_scheduler())
     
+    # 通知 Tauri 外壳后端已就绪（见 src-tauri READY_SENTINEL）
+    print("NULLGRAVITY_READY", flush=True)
+    
     yield
     
     refresh_task.cancel()
@@ -59,12 +73,22 @@ async def lifespan(app: FastAPI):
 app = FastAPI(
     title="NullGravity API",
     description="AI Account Management & Protocol Proxy System",
-    version="0.1.0",
+    version=__version__,
     lifespan=lifespan,
 )
 
+def _resolve_port() -> int:
+    """端口优先取 Tauri 外壳传入的 --port，其次 NULLGRAVITY_PORT 环境变量"""
+    args = sys.argv[1:]
+    if "--port" in args:
+        idx = args.index("--port")
+        if idx + 1 < len(args):
+            return int(args[idx + 1])
+    return int(os.environ.get("NULLGRAVITY_PORT", "8046"))
+
+
 # CORS middleware for frontend communication
-_backend_port = int(os.environ.get("NULLGRAVITY_PORT", "8046"))
+_backend_port = _resolve_port()
 app.add_middleware(
     CORSMiddleware,
     allow_origins=[
@@ -106,16 +130,155 @@ async def websocket_endpoint(websocket: WebSocket):
 @app.get("/api/health")
 async def health_check():
     """Health check endpoint."""
-    return {"status": "ok", "version": "0.1.0"}
+    return {"status": "ok", "version": __version__}
+
+
+def _apply_log_level(level: str) -> None:
+    """按 Tauri 外壳传来的 RUST_LOG 风格级别调整 Python 日志级别"""
+    levels = {
+        "off": logging.CRITICAL + 1,
+        "error": logging.ERROR,
+        "warn": logging.WARNING,
+        "info": logging.INFO,
+        "debug": logging.DEBUG,
+        "trace": logging.DEBUG,
+    }
+    logging.getLogger().setLevel(levels.get(level.lower(), logging.INFO))
+
+
+# 主窗口最小化期间由 Tauri 外壳置位，后台任务可据此跳过非必要的工作
+_paused = threading.Event()
+# 主窗口是否有焦点，后台任务可据此放慢轮询
+_focused = threading.Event()
+
+
+def _dump_state() -> str:
+    """导出所有线程的调用栈和后台状态，供 Tauri 外壳的 sidecar_dump 排查卡死"""
+    import traceback
+    names = {t.ident: t.name for t in threading.enumerate()}
+    parts = [f"paused: {_paused.is_set()}", f"focused: {_focused.is_set()}"]
+    for ident, frame in sys._current_frames().items():
+        parts.append(f"\n## thread {names.get(ident, '?')} ({ident})")
+        parts.append("".join(traceback.format_stack(frame)).rstrip())
+    return "\n".join(parts)
+
+
+def _handle_rpc(frame: dict) -> dict:
+    """处理 Tauri 外壳的 JSON RPC 帧 {id, method, params}"""
+    method = frame.get("method")
+    params = frame.get("params") or {}
+    if not isinstance(params, dict):
+        return {"id": frame["id"], "error": "params must be an object"}
+    if method == "version":
+        return {
+            "id": frame["id"],
+            "result": {"version": app.version, "git_sha": os.environ.get("NULLGRAVITY_GIT_SHA", "unknown")},
+        }
+    if method == "set_log_level":
+        _apply_log_level(params.get("level", "info"))
+        return {"id": frame["id"], "result": None}
+    if method in ("pause", "resume"):
+        if method == "pause":
+            _paused.set()
+        else:
+            _paused.clear()
+        logging.getLogger(__name__).info("Background work %s", "paused" if _paused.is_set() else "resumed")
+        return {"id": frame["id"], "result": {"paused": _paused.is_set()}}
+    if method == "focus":
+        if params.get("focused"):
+            _focused.set()
+        else:
+            _focused.clear()
+        return {"id": frame["id"], "result": None}
+    if method == "dump":
+        return {"id": frame["id"], "result": _dump_state()}
+    if method == "open_url":
+        # 查询串里可能有授权回调的 code，日志只记到路径为止
+        parts = urlsplit(str(params.get("url", "")))
+        logging.getLogger(__name__).info("Opened via deep link: %s://%s%s", parts.scheme, parts.hostname or "", parts.path)
+        return {"id": frame["id"], "result": None}
+    if method == "greet":
+        name = params.get("name", "")
+        if params.get("locale") == "zh":
+            return {"id": frame["id"], "result": f"你好，{name}！来自 NullGravity 后端的问候。"}
+        return {"id": frame["id"], "result": f"Hello, {name}! Greetings from the NullGravity backend."}
+    return {"id": frame["id"], "error": f"unknown method: {method}"}
+
+
+def _stdin_control_loop():
+    """响应 Tauri 外壳通过 stdin 发来的控制行：心跳 ping 和 JSON RPC 帧"""
+    import json
+    if sys.stdin is None:
+        return
+    for line in sys.stdin:
+        line = line.strip()
+        if line == "ping":
+            print("pong", flush=True)
+        elif line.startswith("{"):
+            try:
+                frame = json.loads(line)
+            except ValueError:
+                continue
+            if not isinstance(frame, dict) or "id" not in frame:
+                continue
+            # 单个坏帧只回错误，不能让线程退出，否则之后的 RPC 和心跳全部失效
+            try:
+                reply = _handle_rpc(frame)
+            except Exception as e:
+                logging.getLogger(__name__).exception("RPC %s failed", frame.get("method"))
+                reply = {"id": frame["id"], "error": str(e)}
+            print(json.dumps(reply), flush=True)
+
+
+def _install_signal_handlers() -> None:
+    """Tauri 外壳 send_signal 发来的 Unix 信号；SIGHUP 默认会结束进程，必须接管"""
+    import signal
+    if not hasattr(signal, "SIGHUP"):
+        return
+    log = logging.getLogger(__name__)
+
+    def on_hup(signum, frame):
+        _apply_log_level(os.environ.get("RUST_LOG", "info"))
+        log.info("SIGHUP received, configuration reloaded")
+
+    def on_usr1(signum, frame):
+        print(_dump_state(), file=sys.stderr, flush=True)
+
+    def on_usr2(signum, frame):
+        log.info("SIGUSR2 received")
+
+    signal.signal(signal.SIGHUP, on_hup)
+    signal.signal(signal.SIGUSR1, on_usr1)
+    signal.signal(signal.SIGUSR2, on_usr2)
+
+
+def _port_available(port: int) -> bool:
+    """启动前确认端口可用；被占用时打印 PORT_IN_USE，由 Tauri 外壳换端口重启"""
+    import socket
+    with socket.socket(socket.AF_INET, socket.SOCK_STREAM) as sock:
+        try:
+            sock.bind(("127.0.0.1", port))
+        except OSError:
+            return False
+    return True
 
 
 if __name__ == "__main__":
     import uvicorn
-    port = int(os.environ.get("NULLGRAVITY_PORT", "8046"))
+    if not _port_available(_backend_port):
+        print(f"PORT_IN_USE {_backend_port}", flush=True)
+        sys.exit(98)
+    # --dev / --verbose 只在 Tauri 外壳的调试构建中传入
+    _dev_mode = "--dev" in sys.argv[1:]
+    _apply_log_level("debug" if "--verbose" in sys.argv[1:] else os.environ.get("RUST_LOG", "info"))
+    if _dev_mode:
+        logging.getLogger(__name__).info("Running in development mode")
+    _install_signal_handlers()
+    threading.Thread(target=_stdin_control_loop, daemon=True).start()
     uvicorn.run(
         app,
         host="127.0.0.1",
-        port=port,
+        port=_backend_port,
         reload=False,
         workers=1,
         loop="asyncio",
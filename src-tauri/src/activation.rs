@@ -0,0 +1,157 @@
+use std::fmt;
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_dialog::{DialogExt, MessageDialogKind};
+
+use crate::error::{CommandError, CommandResult};
+use crate::i18n::{self, tr, Msg};
+
+/// 授权检查失败的原因
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", content = "message")]
+pub enum ActivationError {
+    /// 找不到授权文件，message 为预期路径
+    Missing(String),
+    /// 授权文件损坏、签名不符或不属于本机
+    Invalid(String),
+    Expired(String),
+}
+
+impl fmt::Display for ActivationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Missing(path) => write!(f, "License file not found at {}", path),
+            Self::Invalid(e) => write!(f, "License is invalid: {}", e),
+            Self::Expired(e) => write!(f, "License expired: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ActivationError {}
+
+/// 启动 sidecar 之前调用的授权检查，参数为配置目录（见 `paths::config_dir`）
+pub type ActivationCheck = fn(&Path) -> Result<(), ActivationError>;
+
+static CHECK: OnceLock<ActivationCheck> = OnceLock::new();
+
+/// 商业构建在 `run()` 之前替换授权检查，只有第一次设置生效
+pub fn set_activation_check(check: ActivationCheck) {
+    if CHECK.set(check).is_err() {
+        log::warn!("Activation check was already set, ignoring");
+    }
+}
+
+/// 默认构建不做检查，行为与没有这个钩子时完全一样
+pub fn check_activation(_config_dir: &Path) -> Result<(), ActivationError> {
+    Ok(())
+}
+
+/// 授权状态：检查失败时进入受限模式，界面照常运行但不启动后端
+#[derive(Default)]
+pub struct ActivationState(Mutex<Option<ActivationError>>);
+
+impl ActivationState {
+    pub fn is_limited(&self) -> bool {
+        self.error().is_some()
+    }
+
+    fn error(&self) -> Option<ActivationError> {
+        crate::sidecar::lock_recover(&self.0, "Activation state").clone()
+    }
+
+    fn set(&self, error: Option<ActivationError>) {
+        *crate::sidecar::lock_recover(&self.0, "Activation state") = error;
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ActivationMode {
+    Full,
+    /// 未授权：后端不启动，所有需要后端的操作返回 NotActivated
+    Limited,
+}
+
+#[derive(Serialize)]
+pub struct ActivationStatus {
+    pub mode: ActivationMode,
+    pub error: Option<ActivationError>,
+}
+
+/// 运行授权检查并记录结果，返回是否可以启动后端
+pub fn verify(app: &AppHandle) -> bool {
+    let check = CHECK.get().copied().unwrap_or(check_activation);
+    let result = crate::paths::config_dir(app)
+        .map_err(|e| ActivationError::Invalid(e.to_string()))
+        .and_then(|dir| check(&dir));
+    let state = app.state::<ActivationState>();
+    match result {
+        Ok(()) => {
+            if state.is_limited() {
+                log::info!("Activation succeeded, leaving limited mode");
+            }
+            state.set(None);
+            true
+        }
+        Err(e) => {
+            log::warn!("Activation check failed, starting in limited mode: {}", e);
+            state.set(Some(e));
+            false
+        }
+    }
+}
+
+/// spawn 前调用：受限模式下拒绝启动后端
+pub fn ensure_activated(app: &AppHandle) -> CommandResult<()> {
+    match app.state::<ActivationState>().error() {
+        Some(e) => Err(CommandError::NotActivated(e.to_string())),
+        None => Ok(()),
+    }
+}
+
+/// 告诉用户为什么后端没有启动，不阻塞启动流程
+pub fn notify_limited(app: &AppHandle) {
+    let Some(error) = app.state::<ActivationState>().error() else {
+        return;
+    };
+    app.dialog()
+        .message(i18n::format(
+            tr(app, Msg::NotActivated),
+            &[("detail", &error.to_string())],
+        ))
+        .title("NullGravity")
+        .kind(MessageDialogKind::Warning)
+        .show(|_| {});
+}
+
+#[tauri::command]
+pub fn activation_status(app: AppHandle) -> ActivationStatus {
+    let error = app.state::<ActivationState>().error();
+    ActivationStatus {
+        mode: if error.is_some() {
+            ActivationMode::Limited
+        } else {
+            ActivationMode::Full
+        },
+        error,
+    }
+}
+
+/// 用户放好授权文件后重新检查，通过则离开受限模式并启动后端
+#[tauri::command]
+pub async fn retry_activation(app: AppHandle) -> CommandResult<ActivationStatus> {
+    let handle = app.clone();
+    let unlocked = crate::task::blocking(move || {
+        let was_limited = handle.state::<ActivationState>().is_limited();
+        Ok(verify(&handle) && was_limited)
+    })
+    .await?;
+    if unlocked {
+        crate::sidecar::spawn_with_retry(app.clone());
+        crate::binary::watch(app.clone());
+    }
+    Ok(activation_status(app))
+}
@@ -0,0 +1,194 @@
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use log::{info, warn};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use tauri::{AppHandle, Manager};
+
+use crate::config::SettingsState;
+use crate::error::{CommandError, CommandResult};
+use crate::lifecycle::{self, SidecarEvent};
+use crate::sidecar::{lock_recover, SidecarState};
+use crate::BINARY_WATCH_INTERVAL;
+
+/// shell 插件解析 externalBin 的方式：与主程序同目录，Windows 下带 .exe
+pub fn path(bin_name: &str) -> io::Result<PathBuf> {
+    let exe = std::env::current_exe()?;
+    let dir = exe
+        .parent()
+        .ok_or_else(|| io::Error::other("executable has no parent directory"))?;
+    Ok(dir.join(format!("{}{}", bin_name, std::env::consts::EXE_SUFFIX)))
+}
+
+/// 启动前确认可执行文件存在且可执行，避免 spawn 只报出含糊的错误
+pub fn verify(bin_name: &str) -> CommandResult<PathBuf> {
+    let path = path(bin_name)?;
+    probe(&path).map_err(|e| {
+        let problem = match e.kind() {
+            io::ErrorKind::PermissionDenied => "is not executable:",
+            _ => "is missing. Expected it at:",
+        };
+        CommandError::BinaryUnusable(format!(
+            "The backend executable '{}' {}\n{}",
+            bin_name,
+            problem,
+            path.display()
+        ))
+    })?;
+    Ok(path)
+}
+
+/// 缺失（或不是普通文件）返回 NotFound，Unix 上没有执行权限返回 PermissionDenied
+pub fn probe(path: &Path) -> io::Result<()> {
+    #[cfg_attr(not(unix), allow(unused_variables))]
+    let meta = std::fs::metadata(path)
+        .ok()
+        .filter(|meta| meta.is_file())
+        .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if meta.permissions().mode() & 0o111 == 0 {
+            return Err(io::Error::from(io::ErrorKind::PermissionDenied));
+        }
+    }
+    Ok(())
+}
+
+pub fn sha256(path: &Path) -> io::Result<String> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect())
+}
+
+/// 实际会被启动的 sidecar 可执行文件，供支持人员核对版本、发现篡改或下载不完整
+#[derive(Debug, Clone, Serialize)]
+pub struct BinaryInfo {
+    pub path: String,
+    pub size: u64,
+    pub sha256: String,
+}
+
+/// 缓存上次算出的 BinaryInfo；文件被替换（修改时间或大小变化）后才重新计算
+#[derive(Default)]
+pub struct BinaryInfoState(Mutex<Option<(PathBuf, Stamp, BinaryInfo)>>);
+
+#[tauri::command]
+pub async fn sidecar_binary_info(app: AppHandle) -> CommandResult<BinaryInfo> {
+    crate::task::blocking(move || {
+        let bin_name = app.state::<SettingsState>().get().sidecar.bin_name;
+        let path = path(&bin_name)?;
+        let current = stamp(&path)?;
+        let state = app.state::<BinaryInfoState>();
+        let mut cache = lock_recover(&state.0, "Binary info");
+        if let Some((cached_path, cached_stamp, info)) = cache.as_ref() {
+            if *cached_path == path && *cached_stamp == current {
+                return Ok(info.clone());
+            }
+        }
+        let info = BinaryInfo {
+            path: path.to_string_lossy().into_owned(),
+            size: current.len,
+            sha256: sha256(&path)?,
+        };
+        *cache = Some((path, current, info.clone()));
+        Ok(info)
+    })
+    .await
+}
+
+/// 修改时间和大小没变就不重新计算哈希
+#[derive(Clone, PartialEq)]
+struct Stamp {
+    modified: Option<SystemTime>,
+    len: u64,
+}
+
+fn stamp(path: &Path) -> io::Result<Stamp> {
+    let meta = std::fs::metadata(path)?;
+    Ok(Stamp {
+        modified: meta.modified().ok(),
+        len: meta.len(),
+    })
+}
+
+/// 定期检查 sidecar 可执行文件，替换后优雅重启；`watch_binary` 关闭时只空转
+///
+/// 新文件的哈希要连续两次检查一致才重启，避免拿写了一半的文件去启动。
+pub fn watch(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut baseline: Option<(Stamp, String)> = None;
+        let mut pending: Option<String> = None;
+        loop {
+            tokio::time::sleep(BINARY_WATCH_INTERVAL).await;
+            if app.state::<SidecarState>().is_shutting_down() {
+                return;
+            }
+            let settings = app.state::<SettingsState>().get();
+            if !settings.watch_binary {
+                baseline = None;
+                pending = None;
+                continue;
+            }
+            let Ok(path) = path(&settings.sidecar.bin_name) else {
+                continue;
+            };
+
+            let checked = tauri::async_runtime::spawn_blocking(move || {
+                let current = stamp(&path)?;
+                Ok::<_, io::Error>((current, path))
+            })
+            .await;
+            let Ok(Ok((current, path))) = checked else {
+                continue;
+            };
+            if baseline
+                .as_ref()
+                .is_some_and(|(known, _)| *known == current)
+            {
+                pending = None;
+                continue;
+            }
+
+            let hashed = tauri::async_runtime::spawn_blocking(move || sha256(&path)).await;
+            let Ok(Ok(hash)) = hashed else {
+                continue;
+            };
+            match &baseline {
+                // 第一次检查只记录基线
+                None => baseline = Some((current, hash)),
+                Some((_, known)) if *known == hash => baseline = Some((current, hash)),
+                Some(_) if pending.as_deref() == Some(hash.as_str()) => {
+                    info!("Sidecar binary changed ({}), restarting", hash);
+                    baseline = Some((current, hash));
+                    pending = None;
+                    lifecycle::emit(&app, SidecarEvent::Updated);
+                    let handle = app.clone();
+                    let restarted = crate::task::blocking(move || {
+                        crate::sidecar::restart_unless_tripped(&handle)
+                    })
+                    .await;
+                    if let Err(e) = restarted {
+                        warn!("Restart after binary update failed: {}", e);
+                    }
+                }
+                Some(_) => pending = Some(hash),
+            }
+        }
+    });
+}
@@ -0,0 +1,96 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::sidecar::lock_recover;
+
+/// sidecar 崩溃熔断：时间窗内崩溃次数超过上限后停止自动重启，直到显式重试
+#[derive(Default)]
+pub struct CircuitBreaker {
+    crashes: Mutex<VecDeque<Instant>>,
+    open: AtomicBool,
+}
+
+impl CircuitBreaker {
+    /// 记一次崩溃，返回熔断器是否因此打开
+    ///
+    /// 只保留 `window` 内的记录，已经打开时不重复报告。
+    pub fn record_crash(&self, now: Instant, limit: u32, window: Duration) -> bool {
+        let mut crashes = lock_recover(&self.crashes, "Circuit breaker");
+        crashes.push_back(now);
+        while crashes
+            .front()
+            .is_some_and(|t| now.saturating_duration_since(*t) > window)
+        {
+            crashes.pop_front();
+        }
+        if crashes.len() as u32 <= limit {
+            return false;
+        }
+        !self.open.swap(true, Ordering::SeqCst)
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open.load(Ordering::SeqCst)
+    }
+
+    /// 闭合并清空崩溃记录，由 retry_start_sidecar 和用户发起的重启调用
+    pub fn reset(&self) {
+        lock_recover(&self.crashes, "Circuit breaker").clear();
+        self.open.store(false, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WINDOW: Duration = Duration::from_secs(60);
+
+    #[test]
+    fn rapid_crashes_open_the_breaker() {
+        let breaker = CircuitBreaker::default();
+        let start = Instant::now();
+        for i in 0..3 {
+            let now = start + Duration::from_secs(i);
+            assert!(
+                !breaker.record_crash(now, 3, WINDOW),
+                "crash {} opened early",
+                i + 1
+            );
+        }
+        assert!(!breaker.is_open());
+
+        assert!(breaker.record_crash(start + Duration::from_secs(3), 3, WINDOW));
+        assert!(breaker.is_open());
+        // 已经打开后不再重复报告
+        assert!(!breaker.record_crash(start + Duration::from_secs(4), 3, WINDOW));
+        assert!(breaker.is_open());
+    }
+
+    #[test]
+    fn crashes_spread_over_time_keep_it_closed() {
+        let breaker = CircuitBreaker::default();
+        let start = Instant::now();
+        for i in 0..10 {
+            let now = start + Duration::from_secs(i * 30);
+            assert!(!breaker.record_crash(now, 3, WINDOW));
+        }
+        assert!(!breaker.is_open());
+    }
+
+    #[test]
+    fn reset_closes_the_breaker() {
+        let breaker = CircuitBreaker::default();
+        let now = Instant::now();
+        for _ in 0..5 {
+            breaker.record_crash(now, 3, WINDOW);
+        }
+        assert!(breaker.is_open());
+
+        breaker.reset();
+        assert!(!breaker.is_open());
+        assert!(!breaker.record_crash(now, 3, WINDOW));
+    }
+}
@@ -0,0 +1,108 @@
+use std::io::Read;
+use std::process::{Command, Stdio};
+use std::time::Instant;
+
+use crate::sidecar::SIDECAR_NAME;
+use crate::version::SHELL_VERSION;
+use crate::{
+    CLI_VERSION_TIMEOUT, DATA_DIR_FLAG, EXTERNAL_SIDECAR_ENV, EXTERNAL_SIDECAR_FLAG, HEADLESS_ENV,
+    HEADLESS_FLAG,
+};
+
+const VERSION_FLAGS: &[&str] = &["--version", "-V"];
+const HELP_FLAGS: &[&str] = &["--help", "-h"];
+
+/// 处理 `--version` / `--help`：打印到 stdout 后返回 true，调用方应直接退出
+///
+/// 在创建日志文件、窗口和 sidecar 之前调用。
+pub fn handle_info_flags() -> bool {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let has = |flags: &[&str]| args.iter().any(|arg| flags.contains(&arg.as_str()));
+    if has(HELP_FLAGS) {
+        attach_console();
+        print!("{}", usage());
+        return true;
+    }
+    if has(VERSION_FLAGS) {
+        attach_console();
+        println!("NullGravity v{}", SHELL_VERSION);
+        match sidecar_version() {
+            Some(version) => println!("{} v{}", SIDECAR_NAME, version),
+            None => println!("{} (version unavailable)", SIDECAR_NAME),
+        }
+        return true;
+    }
+    false
+}
+
+fn usage() -> String {
+    format!(
+        "NullGravity v{version}
+
+Usage: nullgravity [OPTIONS]
+
+Options:
+  {headless:<18}Run without windows, only supervising the backend (also {env}=1)
+  {data_dir:<18}Keep config, logs and backend data under <path>
+  {external:<18}Use a backend already listening on <port> instead of spawning one
+                    (also {external_env}=<port>)
+  --version, -V     Print the app and backend versions, then exit
+  --help, -h        Print this help, then exit
+",
+        version = SHELL_VERSION,
+        headless = HEADLESS_FLAG,
+        env = HEADLESS_ENV,
+        data_dir = format!("{} <path>", DATA_DIR_FLAG),
+        external = format!("{} <port>", EXTERNAL_SIDECAR_FLAG),
+        external_env = EXTERNAL_SIDECAR_ENV,
+    )
+}
+
+/// 直接运行打包的 sidecar `--version`，超时或失败返回 None
+fn sidecar_version() -> Option<String> {
+    let path = crate::binary::path(SIDECAR_NAME).ok()?;
+    if !path.is_file() {
+        return None;
+    }
+    let mut child = Command::new(&path)
+        .arg("--version")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+
+    let started = Instant::now();
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if started.elapsed() < CLI_VERSION_TIMEOUT => {
+                std::thread::sleep(std::time::Duration::from_millis(20));
+            }
+            _ => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return None;
+            }
+        }
+    };
+    if !status.success() {
+        return None;
+    }
+    let mut out = String::new();
+    child.stdout.take()?.read_to_string(&mut out).ok()?;
+    let version = out.lines().next()?.trim();
+    (!version.is_empty()).then(|| version.trim_start_matches('v').to_string())
+}
+
+/// 发布构建是 windows 子系统，没有控制台；从终端启动时挂到父进程的控制台上才能看到输出
+#[cfg(windows)]
+fn attach_console() {
+    use windows_sys::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(not(windows))]
+fn attach_console() {}
@@ -0,0 +1,337 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, State};
+use tauri_plugin_opener::OpenerExt;
+
+use crate::error::{CommandError, CommandResult};
+use crate::hotkey::{HotkeyState, DEFAULT_SHORTCUT};
+use crate::logging::{LogFormat, LogLevelSync};
+use crate::process::{OutputMode, Priority};
+use crate::redact::{Redactor, DEFAULT_PATTERNS};
+use crate::sidecar::{lock_recover, ReadinessMode, SIDECAR_NAME};
+use crate::task::blocking;
+use crate::theme::ThemePreference;
+use crate::window::{CloseBehavior, WindowState};
+use crate::{
+    CIRCUIT_BREAKER_CRASHES, CIRCUIT_BREAKER_WINDOW, READY_SENTINEL, SHUTDOWN_GRACE,
+    SHUTDOWN_HARD_TIMEOUT,
+};
+
+const SETTINGS_FILE: &str = "settings.toml";
+
+/// 只在启动 sidecar（或应用）时读取的配置，reload_settings 改了也要等重启才生效；其余的立即生效
+const RESTART_KEYS: &[&str] = &[
+    "readiness",
+    "output_mode",
+    "sidecar",
+    "sidecar_priority",
+    "services",
+    "port_range",
+    "persist_session_log",
+];
+
+/// sidecar 的启动参数：可执行文件名、额外参数、环境变量和工作目录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SidecarConfig {
+    /// tauri.conf.json externalBin 中登记的 sidecar 名
+    pub bin_name: String,
+    pub args: Vec<String>,
+    pub env: HashMap<String, String>,
+    pub cwd: Option<PathBuf>,
+    /// 关闭这个进程时的等待时长，不设置时使用全局的 `grace_ms`
+    pub grace_ms: Option<u64>,
+}
+
+impl Default for SidecarConfig {
+    fn default() -> Self {
+        Self {
+            bin_name: SIDECAR_NAME.to_string(),
+            args: Vec::new(),
+            env: HashMap::new(),
+            cwd: None,
+            grace_ms: None,
+        }
+    }
+}
+
+/// 应用配置，保存在配置目录（见 `paths::config_dir`）下的 settings.toml
+///
+/// 文件不存在或解析失败时使用默认值，缺省的字段各自回落到默认值。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub log_level: String,
+    /// 调整日志级别后同步给 sidecar 的方式："rpc"、"restart" 或 "off"
+    pub log_level_sync: LogLevelSync,
+    /// 日志文件格式："text" 或 "json"
+    pub log_format: LogFormat,
+    pub close_behavior: CloseBehavior,
+    /// 窗口主题："dark"、"light" 或 "system"
+    pub theme: ThemePreference,
+    /// 主窗口最小化时通知后端暂停后台任务，还原后恢复
+    pub pause_when_minimized: bool,
+    pub global_shortcut: String,
+    /// 判定 sidecar 就绪的方式，见 `ReadinessMode`
+    pub readiness: ReadinessMode,
+    /// sidecar 输出的读取方式："lines" 按行，或 "chunks" 读到即转发（适合 `\r` 进度条）
+    pub output_mode: OutputMode,
+    /// 监视 sidecar 可执行文件，被替换后自动重启
+    pub watch_binary: bool,
+    /// 定期把最近的 sidecar 输出写到 last-session.log，硬崩溃后下次启动还能看到
+    pub persist_session_log: bool,
+    /// sidecar 输出的脱敏正则
+    pub redact_patterns: Vec<String>,
+    /// 熔断：`circuit_breaker_window_secs` 秒内崩溃超过 `circuit_breaker_crashes` 次后停止自动重启
+    pub circuit_breaker_crashes: u32,
+    pub circuit_breaker_window_secs: u64,
+    /// 关闭 sidecar 时等待其自行退出的时长，超时后强杀
+    pub grace_ms: u64,
+    /// 强杀后再等待的时长，仍未退出则报告 stuck-process
+    pub hard_ms: u64,
+    /// 只在这个端口段内（含两端）为 sidecar 挑端口，比如 `[8000, 8100]`；不设置时由系统分配
+    pub port_range: Option<(u16, u16)>,
+    /// sidecar 的调度优先级："low"、"normal" 或 "high"
+    pub sidecar_priority: Priority,
+    pub sidecar: SidecarConfig,
+    /// 按名字启动的附加 sidecar，见 `services::start_service`；bin_name 缺省时取服务名
+    pub services: HashMap<String, SidecarConfig>,
+    /// 退出时附加服务的关闭顺序，比如 `["indexer"]`；未列出的随后按名字关闭，核心 sidecar 总是最后关闭
+    pub stop_order: Vec<String>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            log_level: "info".to_string(),
+            log_level_sync: LogLevelSync::default(),
+            log_format: LogFormat::default(),
+            close_behavior: CloseBehavior::default(),
+            theme: ThemePreference::default(),
+            pause_when_minimized: false,
+            global_shortcut: DEFAULT_SHORTCUT.to_string(),
+            readiness: ReadinessMode::Sentinel(READY_SENTINEL.to_string()),
+            output_mode: OutputMode::default(),
+            watch_binary: false,
+            persist_session_log: false,
+            redact_patterns: DEFAULT_PATTERNS.iter().map(|p| p.to_string()).collect(),
+            circuit_breaker_crashes: CIRCUIT_BREAKER_CRASHES,
+            circuit_breaker_window_secs: CIRCUIT_BREAKER_WINDOW.as_secs(),
+            grace_ms: SHUTDOWN_GRACE.as_millis() as u64,
+            hard_ms: SHUTDOWN_HARD_TIMEOUT.as_millis() as u64,
+            port_range: None,
+            sidecar_priority: Priority::default(),
+            sidecar: SidecarConfig::default(),
+            services: HashMap::new(),
+            stop_order: Vec::new(),
+        }
+    }
+}
+
+fn settings_path(app: &AppHandle) -> CommandResult<PathBuf> {
+    Ok(crate::paths::config_dir(app)?.join(SETTINGS_FILE))
+}
+
+impl Settings {
+    pub fn load(app: &AppHandle) -> Self {
+        match Self::read(app) {
+            Ok(settings) => settings,
+            Err(e) => {
+                log::warn!("Ignoring unreadable settings: {}", e);
+                Self::default()
+            }
+        }
+    }
+
+    /// 文件不存在时为默认值；读不了（没有权限、不是 UTF-8）或解析失败时报错，免得随后被默认值覆盖
+    fn read(app: &AppHandle) -> CommandResult<Self> {
+        let path = settings_path(app)?;
+        let raw = match std::fs::read_to_string(&path) {
+            Ok(raw) => raw,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(CommandError::Io(format!("{}: {}", path.display(), e))),
+        };
+        let settings = toml::from_str(&raw)
+            .map_err(|e| CommandError::InvalidArgument(format!("{}: {}", path.display(), e)))?;
+        log::info!("Loaded settings from {}", path.display());
+        Ok(settings)
+    }
+
+    fn save(&self, app: &AppHandle) -> CommandResult<()> {
+        let path = settings_path(app)?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let raw = toml::to_string_pretty(self).map_err(|e| CommandError::Io(e.to_string()))?;
+        std::fs::write(&path, raw)?;
+        Ok(())
+    }
+
+    pub fn grace(&self) -> Duration {
+        Duration::from_millis(self.grace_ms)
+    }
+
+    pub fn hard_timeout(&self) -> Duration {
+        Duration::from_millis(self.hard_ms)
+    }
+
+    pub fn circuit_breaker_window(&self) -> Duration {
+        Duration::from_secs(self.circuit_breaker_window_secs)
+    }
+
+    /// 把运行时可以立即生效的配置应用下去；sidecar 相关配置下次启动时生效
+    ///
+    /// 返回可能失败的各项（按配置键）的结果，例如快捷键已被占用。
+    pub fn apply(&self, app: &AppHandle) -> Vec<(&'static str, CommandResult<()>)> {
+        let level = crate::logging::parse_level(&self.log_level).map(crate::logging::apply_level);
+        crate::logging::apply_format(self.log_format);
+        app.state::<Redactor>().set_patterns(&self.redact_patterns);
+        app.state::<WindowState>()
+            .set_close_behavior(self.close_behavior);
+        crate::theme::apply(app, self.theme);
+        let shortcut =
+            crate::hotkey::replace(app, &app.state::<HotkeyState>(), &self.global_shortcut);
+        vec![("log_level", level), ("global_shortcut", shortcut)]
+    }
+}
+
+/// 当前生效的配置
+pub struct SettingsState(Mutex<Settings>);
+
+impl SettingsState {
+    pub fn new(settings: Settings) -> Self {
+        Self(Mutex::new(settings))
+    }
+
+    pub fn get(&self) -> Settings {
+        lock_recover(&self.0, "Settings").clone()
+    }
+}
+
+#[tauri::command]
+pub fn get_settings(state: State<SettingsState>) -> Settings {
+    state.get()
+}
+
+/// 修改部分配置并写回 settings.toml，供各功能自己的设置命令使用
+pub(crate) fn update(app: &AppHandle, change: impl FnOnce(&mut Settings)) -> CommandResult<()> {
+    let state = app.state::<SettingsState>();
+    let mut settings = lock_recover(&state.0, "Settings");
+    change(&mut settings);
+    settings.save(app)
+}
+
+/// reload_settings 中一项配置变化的处理结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeStatus {
+    Applied,
+    /// 已记下，下次启动 sidecar 或应用时生效
+    RequiresRestart,
+    /// 应用失败，原因见 `error`，例如快捷键已被占用
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SettingChange {
+    pub key: String,
+    pub status: ChangeStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// 按顶层字段比较两份配置
+fn diff(old: &Settings, new: &Settings) -> Vec<SettingChange> {
+    let (Ok(serde_json::Value::Object(old)), Ok(serde_json::Value::Object(new))) =
+        (serde_json::to_value(old), serde_json::to_value(new))
+    else {
+        return Vec::new();
+    };
+    new.iter()
+        .filter(|(key, value)| old.get(*key) != Some(*value))
+        .map(|(key, _)| SettingChange {
+            key: key.clone(),
+            status: if RESTART_KEYS.contains(&key.as_str()) {
+                ChangeStatus::RequiresRestart
+            } else {
+                ChangeStatus::Applied
+            },
+            error: None,
+        })
+        .collect()
+}
+
+/// 重新读取 settings.toml，能热更新的立即应用，返回每项变化及是否需要重启
+///
+/// 有变化时同时推送 `settings-changed` 事件。文件解析失败时保持当前配置不变。
+#[tauri::command]
+pub async fn reload_settings(app: AppHandle) -> CommandResult<Vec<SettingChange>> {
+    let handle = app.clone();
+    let changes = blocking(move || {
+        let settings = Settings::read(&app)?;
+        crate::logging::parse_level(&settings.log_level)?;
+        let mut changes = diff(&app.state::<SettingsState>().get(), &settings);
+        if changes.is_empty() {
+            return Ok(changes);
+        }
+        for (key, result) in settings.apply(&app) {
+            let Err(e) = result else {
+                continue;
+            };
+            if let Some(change) = changes.iter_mut().find(|change| change.key == key) {
+                change.status = ChangeStatus::Failed;
+                change.error = Some(e.to_string());
+            }
+        }
+        *lock_recover(&app.state::<SettingsState>().0, "Settings") = settings;
+        for change in &changes {
+            log::info!("Setting '{}' changed: {:?}", change.key, change.status);
+        }
+        let _ = app.emit("settings-changed", &changes);
+        Ok(changes)
+    })
+    .await?;
+
+    // 与 set_log_level 一样按 log_level_sync 同步给 sidecar；RPC 或重启可能要几秒，不等它
+    let level_changed = changes
+        .iter()
+        .any(|change| change.key == "log_level" && change.status == ChangeStatus::Applied);
+    if level_changed {
+        tauri::async_runtime::spawn(async move { crate::logging::sync_level(&handle).await });
+    }
+    Ok(changes)
+}
+
+/// 写入 settings.toml 并立即应用
+#[tauri::command]
+pub async fn save_settings(app: AppHandle, settings: Settings) -> CommandResult<()> {
+    crate::logging::parse_level(&settings.log_level)?;
+    blocking(move || {
+        settings.save(&app)?;
+        let results = settings.apply(&app);
+        *lock_recover(&app.state::<SettingsState>().0, "Settings") = settings;
+        results.into_iter().try_for_each(|(_, result)| result)
+    })
+    .await
+}
+
+/// 在文件管理器中打开配置目录，首次运行时先创建
+#[tauri::command]
+pub async fn open_config_dir(app: AppHandle) -> CommandResult<()> {
+    blocking(move || {
+        let dir = crate::paths::config_dir(&app)?;
+        open_dir(&app, &dir)
+    })
+    .await
+}
+
+pub(crate) fn open_dir(app: &AppHandle, dir: &Path) -> CommandResult<()> {
+    std::fs::create_dir_all(dir)?;
+    app.opener()
+        .open_path(dir.to_string_lossy(), None::<&str>)
+        .map_err(|e| CommandError::Io(e.to_string()))
+}
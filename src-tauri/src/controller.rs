@@ -0,0 +1,508 @@
+use std::io;
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{Mutex, MutexGuard, TryLockError};
+use std::time::{Duration, Instant};
+
+use crate::error::{CommandError, CommandResult};
+use crate::process::{self, Events, OutputMode, ShutdownOutcome, SidecarChild};
+use crate::sidecar::{lock_recover, SpawnFailureKind};
+use crate::{
+    MAX_RESPAWN_ATTEMPTS, RESPAWN_BACKOFF_BASE, RESPAWN_BACKOFF_MAX, RESPAWN_RESET_WINDOW,
+};
+
+/// 被监督的进程，真实实现是 `SidecarChild`
+///
+/// 怎么停由调用方交给 `stop` / `restart` 的闭包决定，真实实现还要处理卡死进程的后台清理。
+pub trait SupervisedProcess: Send + 'static {
+    fn pid(&self) -> u32;
+    fn is_alive(&self) -> bool;
+}
+
+impl SupervisedProcess for SidecarChild {
+    fn pid(&self) -> u32 {
+        SidecarChild::pid(self)
+    }
+
+    fn is_alive(&self) -> bool {
+        SidecarChild::is_alive(self)
+    }
+}
+
+/// 启动进程的方式；测试里换成假实现，模拟崩溃、慢启动和无响应的进程
+pub trait ProcessSpawner: Send + Sync + 'static {
+    type Process: SupervisedProcess;
+    /// 随进程一起交给调用方的东西，真实实现是输出事件队列
+    type Output;
+
+    fn spawn(
+        &self,
+        command: Command,
+        mode: OutputMode,
+    ) -> io::Result<(Self::Process, Self::Output)>;
+}
+
+/// 真实实现：命令由 `sidecar::command_for` 通过 ShellExt 解析出打包的 sidecar，
+/// 这里自行 spawn 才能把它放进独立进程组 / Job Object
+#[derive(Default)]
+pub struct OsSpawner;
+
+impl ProcessSpawner for OsSpawner {
+    type Process = SidecarChild;
+    type Output = Events;
+
+    fn spawn(&self, command: Command, mode: OutputMode) -> io::Result<(SidecarChild, Events)> {
+        let (events, child) = process::spawn_with(command, mode)?;
+        Ok((child, events))
+    }
+}
+
+/// watchdog 看到进程退出时，句柄槽里的情况
+#[derive(Debug, PartialEq, Eq)]
+pub enum Release {
+    /// 退出的正是当前进程，已清理
+    Released,
+    /// 已经有新进程接替（手动重启），与这次退出无关
+    Replaced,
+    /// 槽已空，进程已被 stop 或 restart 取走
+    Vacant,
+}
+
+/// 新进程和 `prepare` 带回的上下文
+pub struct Started<O, T> {
+    pub pid: u32,
+    pub output: O,
+    pub context: T,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ControllerStatus {
+    pub running: bool,
+    pub pid: Option<u32>,
+    pub ready: bool,
+}
+
+/// sidecar 的监督状态：启动、停止、重启、就绪和重启退避，不依赖 AppHandle
+///
+/// 事件推送、输出转发、心跳这些与应用相关的部分由 `sidecar` 围绕它完成。
+pub struct SidecarController<S: ProcessSpawner> {
+    spawner: S,
+    child: Mutex<Option<S::Process>>,
+    /// 主动关闭时置位，watchdog 看到后不再重启
+    shutting_down: AtomicBool,
+    /// 重启或关闭前置位，watchdog 据此把这次退出当作主动停止；新进程起来后复位
+    intentional_stop: AtomicBool,
+    /// 当前进程是否已就绪
+    ready: AtomicBool,
+    /// 连续重启次数，进程平稳运行超过 RESPAWN_RESET_WINDOW 后清零
+    respawn_attempts: AtomicU32,
+    started_at: Mutex<Option<Instant>>,
+    /// 最近一次 spawn 失败的分类，之后任意一次启动成功即清空
+    spawn_error: Mutex<Option<SpawnFailureKind>>,
+}
+
+impl<S: ProcessSpawner + Default> Default for SidecarController<S> {
+    fn default() -> Self {
+        Self::new(S::default())
+    }
+}
+
+impl<S: ProcessSpawner> SidecarController<S> {
+    pub fn new(spawner: S) -> Self {
+        Self {
+            spawner,
+            child: Mutex::new(None),
+            shutting_down: AtomicBool::new(false),
+            intentional_stop: AtomicBool::new(false),
+            ready: AtomicBool::new(false),
+            respawn_attempts: AtomicU32::new(0),
+            started_at: Mutex::new(None),
+            spawn_error: Mutex::new(None),
+        }
+    }
+
+    pub fn lock_child(&self) -> MutexGuard<'_, Option<S::Process>> {
+        lock_recover(&self.child, "Sidecar child")
+    }
+
+    /// 不等锁，锁被占用时返回 None；只在 panic 路径使用
+    pub fn try_lock_child(&self) -> Option<MutexGuard<'_, Option<S::Process>>> {
+        match self.child.try_lock() {
+            Ok(guard) => Some(guard),
+            Err(TryLockError::Poisoned(poisoned)) => Some(poisoned.into_inner()),
+            Err(TryLockError::WouldBlock) => None,
+        }
+    }
+
+    pub fn pid(&self) -> Option<u32> {
+        self.lock_child().as_ref().map(|c| c.pid())
+    }
+
+    /// 进程已退出但 watchdog 还没来得及清理时，`pid` 仍有值而 `running` 为 false
+    pub fn status(&self) -> ControllerStatus {
+        let guard = self.lock_child();
+        ControllerStatus {
+            running: guard.as_ref().is_some_and(|c| c.is_alive()),
+            pid: guard.as_ref().map(|c| c.pid()),
+            ready: self.is_ready(),
+        }
+    }
+
+    /// 没有进程在运行时启动；已经在运行时返回 `Ok(None)`，不重复启动
+    ///
+    /// `prepare` 在持有 child 锁时调用，给出启动命令、输出模式和调用方要带回的上下文。
+    pub fn start<T>(
+        &self,
+        prepare: impl FnOnce() -> CommandResult<(Command, OutputMode, T)>,
+    ) -> CommandResult<Option<Started<S::Output, T>>> {
+        if self.is_shutting_down() {
+            return Err(CommandError::ShuttingDown);
+        }
+        let mut slot = self.lock_child();
+        if slot.is_some() {
+            return Ok(None);
+        }
+        self.spawn_into(&mut slot, prepare).map(Some)
+    }
+
+    /// 全程持有 child 锁：交给 `stop` 停掉旧进程后再 `prepare`（旧进程已释放端口），并发调用只会排队执行
+    pub fn restart<T>(
+        &self,
+        stop: impl FnOnce(S::Process),
+        prepare: impl FnOnce() -> CommandResult<(Command, OutputMode, T)>,
+    ) -> CommandResult<Started<S::Output, T>> {
+        if self.is_shutting_down() {
+            return Err(CommandError::ShuttingDown);
+        }
+        let mut slot = self.lock_child();
+        if let Some(process) = slot.take() {
+            self.mark_intentional_stop();
+            stop(process);
+        }
+        self.reset_respawns();
+        self.spawn_into(&mut slot, prepare)
+    }
+
+    /// 取走当前进程交给 `stop`，没有进程时返回 None；之后的退出不算崩溃
+    pub fn stop(
+        &self,
+        stop: impl FnOnce(S::Process) -> ShutdownOutcome,
+    ) -> Option<ShutdownOutcome> {
+        let process = self.lock_child().take()?;
+        self.mark_intentional_stop();
+        Some(stop(process))
+    }
+
+    fn spawn_into<T>(
+        &self,
+        slot: &mut Option<S::Process>,
+        prepare: impl FnOnce() -> CommandResult<(Command, OutputMode, T)>,
+    ) -> CommandResult<Started<S::Output, T>> {
+        let (command, mode, context) = prepare()?;
+        let (process, output) = self.spawner.spawn(command, mode).map_err(|e| {
+            self.set_spawn_error(Some(SpawnFailureKind::classify(&e)));
+            CommandError::SpawnFailed(e.to_string())
+        })?;
+        let pid = process.pid();
+        *slot = Some(process);
+        self.ready.store(false, Ordering::SeqCst);
+        *lock_recover(&self.started_at, "Sidecar start time") = Some(Instant::now());
+        self.set_spawn_error(None);
+        self.intentional_stop.store(false, Ordering::SeqCst);
+        Ok(Started {
+            pid,
+            output,
+            context,
+        })
+    }
+
+    /// 进程退出后由 watchdog 调用，只清理属于这个 PID 的句柄，避免误删已经换上的新进程
+    pub fn release(&self, pid: u32) -> Release {
+        let mut slot = self.lock_child();
+        match slot.as_ref().map(|c| c.pid()) {
+            Some(current) if current == pid => {
+                slot.take();
+                self.ready.store(false, Ordering::SeqCst);
+                Release::Released
+            }
+            Some(_) => Release::Replaced,
+            None => Release::Vacant,
+        }
+    }
+
+    /// 首次就绪返回 true
+    pub fn mark_ready(&self) -> bool {
+        !self.ready.swap(true, Ordering::SeqCst)
+    }
+
+    /// 失去就绪（外部后端断开），之前是就绪的返回 true
+    pub fn mark_not_ready(&self) -> bool {
+        self.ready.swap(false, Ordering::SeqCst)
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.ready.load(Ordering::SeqCst)
+    }
+
+    pub fn mark_intentional_stop(&self) {
+        self.intentional_stop.store(true, Ordering::SeqCst);
+    }
+
+    /// 消费主动停止标记：这次退出是否是我们要求的
+    pub fn take_intentional_stop(&self) -> bool {
+        self.intentional_stop.swap(false, Ordering::SeqCst)
+    }
+
+    pub fn begin_shutdown(&self) {
+        self.shutting_down.store(true, Ordering::SeqCst);
+        self.mark_intentional_stop();
+    }
+
+    pub fn is_shutting_down(&self) -> bool {
+        self.shutting_down.load(Ordering::SeqCst)
+    }
+
+    /// 当前进程已运行的时长
+    pub fn uptime(&self) -> Option<Duration> {
+        lock_recover(&self.started_at, "Sidecar start time").map(|t| t.elapsed())
+    }
+
+    pub fn reset_respawns(&self) {
+        self.respawn_attempts.store(0, Ordering::SeqCst);
+    }
+
+    /// 进程平稳运行超过 RESPAWN_RESET_WINDOW 后才退出的，不计入连续重启
+    pub fn reset_respawns_if_stable(&self) {
+        if self.uptime().is_some_and(|t| t >= RESPAWN_RESET_WINDOW) {
+            self.reset_respawns();
+        }
+    }
+
+    /// 下一次自动重启的序号和等待时长，超过 MAX_RESPAWN_ATTEMPTS 后返回 None
+    pub fn next_respawn(&self) -> Option<(u32, Duration)> {
+        let attempt = self.respawn_attempts.fetch_add(1, Ordering::SeqCst) + 1;
+        (attempt <= MAX_RESPAWN_ATTEMPTS).then(|| (attempt, backoff_delay(attempt)))
+    }
+
+    pub fn last_spawn_error(&self) -> Option<SpawnFailureKind> {
+        lock_recover(&self.spawn_error, "Sidecar spawn error").clone()
+    }
+
+    pub fn set_spawn_error(&self, error: Option<SpawnFailureKind>) {
+        *lock_recover(&self.spawn_error, "Sidecar spawn error") = error;
+    }
+}
+
+/// 500ms 起步指数退避，封顶 RESPAWN_BACKOFF_MAX
+fn backoff_delay(attempt: u32) -> Duration {
+    let factor = 1u32 << attempt.saturating_sub(1).min(16);
+    RESPAWN_BACKOFF_BASE
+        .saturating_mul(factor)
+        .min(RESPAWN_BACKOFF_MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+
+    /// 假进程：`alive` 由测试直接改写来模拟崩溃；`unresponsive` 时无视停止请求
+    struct FakeProcess {
+        pid: u32,
+        alive: Arc<AtomicBool>,
+        unresponsive: bool,
+    }
+
+    impl SupervisedProcess for FakeProcess {
+        fn pid(&self) -> u32 {
+            self.pid
+        }
+
+        fn is_alive(&self) -> bool {
+            self.alive.load(Ordering::SeqCst)
+        }
+    }
+
+    impl FakeProcess {
+        /// 模拟 `stop_child`：无响应的进程强杀后仍然存活
+        fn stop(&self) -> ShutdownOutcome {
+            if !self.is_alive() {
+                return ShutdownOutcome::AlreadyDead;
+            }
+            if self.unresponsive {
+                return ShutdownOutcome::Stuck;
+            }
+            self.alive.store(false, Ordering::SeqCst);
+            ShutdownOutcome::Graceful
+        }
+    }
+
+    #[derive(Default)]
+    struct FakeSpawner {
+        next_pid: AtomicU32,
+        /// 接下来这么多次 spawn 以 NotFound 失败
+        failures: AtomicU32,
+        unresponsive: AtomicBool,
+    }
+
+    impl ProcessSpawner for FakeSpawner {
+        type Process = FakeProcess;
+        /// 交给测试的存活标记，置为 false 即模拟崩溃
+        type Output = Arc<AtomicBool>;
+
+        fn spawn(
+            &self,
+            _command: Command,
+            _mode: OutputMode,
+        ) -> io::Result<(FakeProcess, Arc<AtomicBool>)> {
+            let failing = self
+                .failures
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                .is_ok();
+            if failing {
+                return Err(io::Error::from(io::ErrorKind::NotFound));
+            }
+            let alive = Arc::new(AtomicBool::new(true));
+            let process = FakeProcess {
+                pid: 100 + self.next_pid.fetch_add(1, Ordering::SeqCst),
+                alive: alive.clone(),
+                unresponsive: self.unresponsive.load(Ordering::SeqCst),
+            };
+            Ok((process, alive))
+        }
+    }
+
+    fn prepare() -> CommandResult<(Command, OutputMode, ())> {
+        Ok((Command::new("fake-sidecar"), OutputMode::Lines, ()))
+    }
+
+    fn start(controller: &SidecarController<FakeSpawner>) -> (u32, Arc<AtomicBool>) {
+        let started = controller
+            .start(prepare)
+            .unwrap()
+            .expect("a process was already running");
+        (started.pid, started.output)
+    }
+
+    #[test]
+    fn slow_start_is_not_ready_until_marked() {
+        let controller = SidecarController::<FakeSpawner>::default();
+        let (pid, _) = start(&controller);
+        assert_eq!(
+            controller.status(),
+            ControllerStatus {
+                running: true,
+                pid: Some(pid),
+                ready: false
+            }
+        );
+        assert!(controller.mark_ready());
+        assert!(!controller.mark_ready(), "ready reported twice");
+        assert!(controller.status().ready);
+
+        // 已经在运行时不重复启动
+        assert!(controller.start(prepare).unwrap().is_none());
+    }
+
+    #[test]
+    fn stop_is_not_treated_as_a_crash() {
+        let controller = SidecarController::<FakeSpawner>::default();
+        let (pid, alive) = start(&controller);
+        let outcome = controller.stop(|p| p.stop());
+        assert_eq!(outcome, Some(ShutdownOutcome::Graceful));
+        assert!(!alive.load(Ordering::SeqCst));
+        assert_eq!(controller.release(pid), Release::Vacant);
+        assert!(controller.take_intentional_stop());
+        assert!(!controller.status().running);
+        assert_eq!(controller.stop(|p| p.stop()), None);
+    }
+
+    #[test]
+    fn crashes_back_off_and_eventually_give_up() {
+        let controller = SidecarController::<FakeSpawner>::default();
+        let (pid, alive) = start(&controller);
+        controller.mark_ready();
+
+        alive.store(false, Ordering::SeqCst);
+        assert!(!controller.status().running);
+        assert_eq!(controller.release(pid), Release::Released);
+        assert!(!controller.take_intentional_stop());
+        assert!(!controller.is_ready());
+
+        let mut previous = Duration::ZERO;
+        for expected in 1..=MAX_RESPAWN_ATTEMPTS {
+            let (attempt, delay) = controller.next_respawn().unwrap();
+            assert_eq!(attempt, expected);
+            assert!(delay >= previous && delay <= RESPAWN_BACKOFF_MAX);
+            previous = delay;
+        }
+        assert_eq!(controller.next_respawn(), None);
+
+        controller.reset_respawns();
+        assert_eq!(controller.next_respawn(), Some((1, RESPAWN_BACKOFF_BASE)));
+    }
+
+    #[test]
+    fn restart_replaces_the_process() {
+        let controller = SidecarController::<FakeSpawner>::default();
+        let (old, old_alive) = start(&controller);
+        let new = controller
+            .restart(
+                |p| {
+                    p.stop();
+                },
+                prepare,
+            )
+            .unwrap()
+            .pid;
+        assert_ne!(old, new);
+        assert!(!old_alive.load(Ordering::SeqCst));
+        // 旧进程的 watchdog 晚到时不能误删新进程
+        assert_eq!(controller.release(old), Release::Replaced);
+        assert_eq!(controller.pid(), Some(new));
+    }
+
+    #[test]
+    fn unresponsive_process_is_reported_stuck() {
+        let controller = SidecarController::<FakeSpawner>::default();
+        controller
+            .spawner
+            .unresponsive
+            .store(true, Ordering::SeqCst);
+        let (_, alive) = start(&controller);
+        let outcome = controller.stop(|p| p.stop());
+        assert_eq!(outcome, Some(ShutdownOutcome::Stuck));
+        assert!(alive.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn failed_spawn_is_recorded_until_a_successful_start() {
+        let controller = SidecarController::<FakeSpawner>::default();
+        controller.spawner.failures.store(1, Ordering::SeqCst);
+        let result = controller.start(prepare);
+        assert!(matches!(result, Err(CommandError::SpawnFailed(_))));
+        assert_eq!(
+            controller.last_spawn_error(),
+            Some(SpawnFailureKind::BinaryNotFound)
+        );
+        assert!(!controller.status().running);
+
+        start(&controller);
+        assert_eq!(controller.last_spawn_error(), None);
+    }
+
+    #[test]
+    fn shutdown_refuses_to_start() {
+        let controller = SidecarController::<FakeSpawner>::default();
+        controller.begin_shutdown();
+        assert!(matches!(
+            controller.start(prepare),
+            Err(CommandError::ShuttingDown)
+        ));
+        assert!(matches!(
+            controller.restart(|_| {}, prepare),
+            Err(CommandError::ShuttingDown)
+        ));
+    }
+}
@@ -0,0 +1,89 @@
+use std::backtrace::Backtrace;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+use std::thread::ThreadId;
+
+use tauri::AppHandle;
+use tauri_plugin_clipboard_manager::ClipboardExt;
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+
+use crate::i18n::{tr, Msg};
+use crate::sidecar;
+
+static APP: OnceLock<AppHandle> = OnceLock::new();
+static MAIN_THREAD: OnceLock<ThreadId> = OnceLock::new();
+static HANDLING: AtomicBool = AtomicBool::new(false);
+
+/// 安装 panic hook：记录消息和调用栈，关闭 sidecar，弹出崩溃对话框后退出
+pub fn install_panic_hook() {
+    let _ = MAIN_THREAD.set(std::thread::current().id());
+
+    std::panic::set_hook(Box::new(|info| {
+        let message = info
+            .payload()
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| info.payload().downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".to_string());
+        let location = info
+            .location()
+            .map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column()))
+            .unwrap_or_else(|| "unknown location".to_string());
+        let details = format!(
+            "panic at {}: {}\n\n{}",
+            location,
+            message,
+            Backtrace::force_capture()
+        );
+        log::error!("{}", details);
+        log::logger().flush();
+
+        if HANDLING.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let Some(app) = APP.get().cloned() else {
+            return;
+        };
+
+        // panic 线程可能还持有 sidecar 的锁，清理工作放到独立线程，等它解开
+        let on_main_thread = MAIN_THREAD.get() == Some(&std::thread::current().id());
+        if on_main_thread {
+            // 主线程 panic 后事件循环不再可用，只能尽力直接结束 sidecar
+            sidecar::kill_now(&app);
+        }
+        std::thread::spawn(move || handle_crash(app, details, on_main_thread));
+    }));
+}
+
+/// setup 中调用，让 panic hook 能拿到 AppHandle
+pub fn attach(app: &AppHandle) {
+    let _ = APP.set(app.clone());
+}
+
+fn handle_crash(app: AppHandle, details: String, on_main_thread: bool) {
+    crate::session::flush(&app);
+    let outcome = sidecar::shutdown_sidecar(&app);
+    log::error!("Sidecar shutdown after panic: {:?}", outcome);
+    if on_main_thread {
+        return;
+    }
+
+    let copy = app
+        .dialog()
+        .message(format!(
+            "{}\n\n{}",
+            tr(&app, Msg::AppCrashed),
+            details.lines().next().unwrap_or_default()
+        ))
+        .title(tr(&app, Msg::AppCrashedTitle))
+        .kind(MessageDialogKind::Error)
+        .buttons(MessageDialogButtons::OkCancelCustom(
+            tr(&app, Msg::CopyDetails).into(),
+            tr(&app, Msg::Close).into(),
+        ))
+        .blocking_show();
+    if copy {
+        let _ = app.clipboard().write_text(details);
+    }
+    app.exit(101);
+}
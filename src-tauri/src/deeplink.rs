@@ -0,0 +1,121 @@
+use std::sync::Mutex;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_deep_link::DeepLinkExt;
+
+use crate::sidecar::{lock_recover, SidecarState};
+use crate::RPC_TIMEOUT;
+
+/// 注册的自定义 URL scheme
+pub const SCHEME: &str = "nullgravity";
+
+/// sidecar 就绪前收到的链接，就绪后按顺序转发
+#[derive(Default)]
+pub struct DeepLinkState(Mutex<Vec<String>>);
+
+#[derive(Clone, Serialize)]
+struct DeepLink {
+    url: String,
+}
+
+/// 监听链接打开事件，并处理冷启动时随启动参数带进来的链接
+///
+/// 应用已在运行时再点链接，由 single-instance 插件转交到这里，不会启动第二个进程。
+pub fn init(app: &AppHandle) {
+    #[cfg(any(windows, target_os = "linux"))]
+    if let Err(e) = app.deep_link().register(SCHEME) {
+        log::warn!("Failed to register {}:// scheme: {}", SCHEME, e);
+    }
+
+    let handle = app.clone();
+    app.deep_link().on_open_url(move |event| {
+        for url in event.urls() {
+            open(&handle, url.to_string());
+        }
+    });
+
+    match app.deep_link().get_current() {
+        Ok(Some(urls)) => {
+            for url in urls {
+                open(app, url.to_string());
+            }
+        }
+        Ok(None) => {}
+        Err(e) => log::warn!("Failed to read the launch deep link: {}", e),
+    }
+}
+
+/// 通知前端并转发给 sidecar；sidecar 未就绪时先排队
+fn open(app: &AppHandle, url: String) {
+    log::info!("Received deep link {}", describe(&url));
+    let _ = app.emit("deep-link", DeepLink { url: url.clone() });
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.unminimize();
+    }
+    crate::show_main_window(app);
+
+    let state = app.state::<DeepLinkState>();
+    let mut pending = lock_recover(&state.0, "Deep links");
+    // 与 flush 共用这把锁，就绪标记在两者之间翻转也不会漏掉或重复
+    if app.state::<SidecarState>().is_ready() {
+        drop(pending);
+        forward(app, url);
+    } else {
+        pending.push(url);
+    }
+}
+
+/// sidecar 就绪后转发排队的链接
+pub fn flush(app: &AppHandle) {
+    let state = app.state::<DeepLinkState>();
+    let urls: Vec<String> = lock_recover(&state.0, "Deep links").drain(..).collect();
+    for url in urls {
+        forward(app, url);
+    }
+}
+
+fn forward(app: &AppHandle, url: String) {
+    let handle = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let params = serde_json::json!({ "url": url });
+        if let Err(e) = crate::rpc::call(&handle, "open_url", params, RPC_TIMEOUT).await {
+            log::warn!(
+                "Failed to forward deep link {} to the sidecar: {}",
+                describe(&url),
+                e
+            );
+        }
+    });
+}
+
+/// 写日志用的链接：只保留 scheme、host 和 path，查询串和片段里可能有授权回调的 code
+fn describe(url: &str) -> String {
+    match tauri::Url::parse(url) {
+        Ok(parsed) => format!(
+            "{}://{}{}",
+            parsed.scheme(),
+            parsed.host_str().unwrap_or_default(),
+            parsed.path()
+        ),
+        Err(_) => "<unparsable link>".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn described_links_drop_query_and_fragment() {
+        assert_eq!(
+            describe("nullgravity://auth/callback?code=secret&state=x#frag"),
+            "nullgravity://auth/callback"
+        );
+        assert_eq!(
+            describe("nullgravity://user:pw@open/settings"),
+            "nullgravity://open/settings"
+        );
+        assert_eq!(describe("not a url"), "<unparsable link>");
+    }
+}
@@ -0,0 +1,328 @@
+use std::fs::File;
+use std::io::Write;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde::Serialize;
+use serde_json::Value;
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System};
+use tauri::{AppHandle, Manager};
+use time::macros::format_description;
+use time::OffsetDateTime;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::config::{Settings, SettingsState};
+use crate::error::{CommandError, CommandResult};
+use crate::lifecycle::SidecarEvent;
+use crate::process::ExitStatus;
+use crate::redact::{Redactor, MASK};
+use crate::sidecar::SidecarState;
+use crate::startup::{StartupMilestone, StartupTimings};
+use crate::task::blocking;
+
+/// 诊断包放在数据目录（见 `paths::data_dir`）下的这个子目录
+const EXPORT_DIR: &str = "diagnostics";
+/// sidecar_dump 等待后端自行导出的时长，超时后改为外部采集
+const DUMP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 散落在各处的运行状态汇总，随生命周期事件更新，供“报告问题”附带
+#[derive(Default)]
+pub struct DiagnosticsState {
+    /// 本次会话内 watchdog 自动重启的总次数，不会像退避计数那样清零
+    restart_count: AtomicU32,
+    last_exit: Mutex<Option<ExitStatus>>,
+}
+
+impl DiagnosticsState {
+    pub fn observe(&self, event: &SidecarEvent) {
+        if let SidecarEvent::Restarting { .. } = event {
+            self.restart_count.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn record_exit(&self, exit: Option<ExitStatus>) {
+        if exit.is_some() {
+            *self.last_exit.lock().unwrap() = exit;
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct Diagnostics {
+    pub app_version: &'static str,
+    pub sidecar_running: bool,
+    pub sidecar_pid: Option<u32>,
+    pub sidecar_uptime_secs: Option<u64>,
+    pub restart_count: u32,
+    pub last_exit_code: Option<i32>,
+    pub log_file_path: Option<String>,
+    /// 本次启动各阶段的耗时，见 `startup::mark`
+    pub startup: Vec<StartupMilestone>,
+}
+
+#[tauri::command]
+pub async fn diagnostics(app: AppHandle) -> CommandResult<Diagnostics> {
+    blocking(move || Ok(collect(&app))).await
+}
+
+/// 运行环境的基本信息
+#[derive(Serialize)]
+struct SystemInfo {
+    os: &'static str,
+    arch: &'static str,
+    os_version: Option<String>,
+    kernel_version: Option<String>,
+    app_version: &'static str,
+}
+
+/// 把日志、当前配置、diagnostics() 的结果和系统信息打包成 zip，返回文件路径
+///
+/// 日志和配置都先经过 Redactor 脱敏再写入。
+#[tauri::command]
+pub async fn export_diagnostics(app: AppHandle) -> CommandResult<String> {
+    blocking(move || {
+        log::logger().flush();
+        let path = export(&app)?;
+        log::info!("Diagnostics exported to {}", path.display());
+        Ok(path.to_string_lossy().into_owned())
+    })
+    .await
+}
+
+/// 请求 sidecar 导出线程栈等内部状态，写入诊断目录并返回文件路径
+///
+/// 后端卡死、超时未响应时改为从系统侧采集（Linux 上读取 /proc），文件开头注明是外部采集。
+#[tauri::command]
+pub async fn sidecar_dump(app: AppHandle) -> CommandResult<String> {
+    let pid = crate::sidecar::current_pid(&app).ok_or(CommandError::SidecarNotRunning)?;
+    let reply = crate::rpc::call(&app, "dump", Value::Null, DUMP_TIMEOUT).await;
+    blocking(move || {
+        let content = match reply {
+            Ok(dump) => {
+                let dump = match dump {
+                    Value::String(text) => text,
+                    other => to_json(&other)?,
+                };
+                format!(
+                    "# Sidecar PID={} dump (reported by the backend)\n\n{}\n",
+                    pid, dump
+                )
+            }
+            Err(e) => {
+                log::warn!(
+                    "Sidecar PID={} did not answer the dump request ({}), capturing externally",
+                    pid,
+                    e
+                );
+                format!(
+                    "# Sidecar PID={} dump (external capture, backend did not respond: {})\n\n{}",
+                    pid,
+                    e,
+                    external_dump(pid)
+                )
+            }
+        };
+        let redactor = app.state::<Redactor>();
+        let content = content
+            .lines()
+            .map(|line| redactor.redact(line.to_string()))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let path = export_dir(&app)?.join(format!("sidecar-dump-{}.txt", stamp()));
+        std::fs::write(&path, content)?;
+        log::info!("Sidecar dump written to {}", path.display());
+        Ok(path.to_string_lossy().into_owned())
+    })
+    .await
+}
+
+/// 不依赖后端配合，从系统侧能看到的进程状态
+fn external_dump(pid: u32) -> String {
+    let mut out = String::new();
+    let mut sys = System::new();
+    sys.refresh_processes_specifics(
+        ProcessesToUpdate::Some(&[Pid::from_u32(pid)]),
+        true,
+        ProcessRefreshKind::nothing().with_cpu().with_memory(),
+    );
+    match sys.process(Pid::from_u32(pid)) {
+        Some(process) => out.push_str(&format!(
+            "status: {}\nmemory_bytes: {}\nrun_time_secs: {}\n",
+            process.status(),
+            process.memory(),
+            process.run_time()
+        )),
+        None => out.push_str("process not found\n"),
+    }
+    out.push_str(&thread_stacks(pid));
+    out
+}
+
+/// 逐个线程读取 /proc/<pid>/task 下的状态、等待点和内核栈；stack 通常需要 root，读不到时注明
+#[cfg(target_os = "linux")]
+fn thread_stacks(pid: u32) -> String {
+    let task_dir = format!("/proc/{}/task", pid);
+    let Ok(entries) = std::fs::read_dir(&task_dir) else {
+        return format!("\n{} is not readable\n", task_dir);
+    };
+    let mut tids: Vec<String> = entries
+        .flatten()
+        .map(|e| e.file_name().to_string_lossy().into_owned())
+        .collect();
+    tids.sort_by_key(|tid| tid.parse::<u32>().unwrap_or(u32::MAX));
+
+    let read = |tid: &str, file: &str| {
+        std::fs::read_to_string(format!("{}/{}/{}", task_dir, tid, file))
+            .map(|s| s.trim_end().to_string())
+            .unwrap_or_else(|e| format!("<unavailable: {}>", e))
+    };
+    let mut out = String::new();
+    for tid in tids {
+        let state = read(&tid, "status")
+            .lines()
+            .find(|line| line.starts_with("State:"))
+            .unwrap_or("State: ?")
+            .to_string();
+        out.push_str(&format!(
+            "\n## thread {} ({})\n{}\nwchan: {}\n{}\n",
+            tid,
+            read(&tid, "comm"),
+            state,
+            read(&tid, "wchan"),
+            read(&tid, "stack")
+        ));
+    }
+    out
+}
+
+#[cfg(not(target_os = "linux"))]
+fn thread_stacks(_pid: u32) -> String {
+    "\nPer-thread stacks can only be captured externally on Linux\n".to_string()
+}
+
+fn export_dir(app: &AppHandle) -> CommandResult<std::path::PathBuf> {
+    let dir = crate::paths::data_dir(app)?.join(EXPORT_DIR);
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+fn stamp() -> String {
+    OffsetDateTime::now_utc()
+        .format(format_description!(
+            "[year][month][day]-[hour][minute][second]"
+        ))
+        .unwrap_or_default()
+}
+
+fn export(app: &AppHandle) -> CommandResult<std::path::PathBuf> {
+    let path = export_dir(app)?.join(format!("nullgravity-diagnostics-{}.zip", stamp()));
+
+    let redactor = app.state::<Redactor>();
+    let redact = |text: &str| {
+        text.lines()
+            .map(|line| redactor.redact(line.to_string()))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    let settings = toml::to_string_pretty(&mask_env(app.state::<SettingsState>().get()))
+        .map_err(|e| CommandError::Io(e.to_string()))?;
+    let system = SystemInfo {
+        os: std::env::consts::OS,
+        arch: std::env::consts::ARCH,
+        os_version: System::long_os_version(),
+        kernel_version: System::kernel_version(),
+        app_version: crate::version::SHELL_VERSION,
+    };
+
+    let mut zip = ZipWriter::new(File::create(&path)?);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    let mut add = |name: &str, content: &str| -> CommandResult<()> {
+        zip.start_file(name, options).map_err(zip_error)?;
+        zip.write_all(content.as_bytes())?;
+        Ok(())
+    };
+    add("diagnostics.json", &to_json(&collect(app))?)?;
+    add("system.json", &to_json(&system)?)?;
+    add("settings.toml", &redact(&settings))?;
+    for log in crate::logging::log_files(app).unwrap_or_default() {
+        let Some(name) = log.file_name() else {
+            continue;
+        };
+        match std::fs::read(&log) {
+            Ok(raw) => add(
+                &format!("logs/{}", name.to_string_lossy()),
+                &redact(&String::from_utf8_lossy(&raw)),
+            )?,
+            Err(e) => log::warn!("Skipping {} in diagnostics: {}", log.display(), e),
+        }
+    }
+    zip.finish().map_err(zip_error)?;
+    Ok(path)
+}
+
+/// 环境变量里常放凭据，不管键名是什么都整体替换掉，不依赖逐行的正则脱敏
+fn mask_env(mut settings: Settings) -> Settings {
+    let configs = std::iter::once(&mut settings.sidecar).chain(settings.services.values_mut());
+    for config in configs {
+        for value in config.env.values_mut() {
+            *value = MASK.to_string();
+        }
+    }
+    settings
+}
+
+fn to_json<T: Serialize>(value: &T) -> CommandResult<String> {
+    serde_json::to_string_pretty(value).map_err(|e| CommandError::Io(e.to_string()))
+}
+
+fn zip_error(e: zip::result::ZipError) -> CommandError {
+    CommandError::Io(e.to_string())
+}
+
+fn collect(app: &AppHandle) -> Diagnostics {
+    let diagnostics = app.state::<DiagnosticsState>();
+    let sidecar_pid = crate::sidecar::current_pid(app);
+    let sidecar_uptime_secs = sidecar_pid
+        .and_then(|_| app.state::<SidecarState>().uptime())
+        .map(|d| d.as_secs());
+    let last_exit_code = diagnostics.last_exit.lock().unwrap().and_then(|s| s.code);
+    Diagnostics {
+        app_version: crate::version::SHELL_VERSION,
+        sidecar_running: sidecar_pid.is_some(),
+        sidecar_pid,
+        sidecar_uptime_secs,
+        restart_count: diagnostics.restart_count.load(Ordering::Relaxed),
+        last_exit_code,
+        log_file_path: crate::logging::log_file(app).ok(),
+        startup: app.state::<StartupTimings>().milestones(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn env_values_are_masked_in_every_sidecar() {
+        let mut settings = Settings::default();
+        settings
+            .sidecar
+            .env
+            .insert("DATABASE_URL".into(), "postgres://u:pw@db".into());
+        let mut indexer = crate::config::SidecarConfig::default();
+        indexer.env.insert("HF".into(), "hf_abc".into());
+        indexer.args.push("--workers=2".into());
+        settings.services.insert("indexer".into(), indexer);
+
+        let masked = mask_env(settings);
+        assert_eq!(masked.sidecar.env["DATABASE_URL"], MASK);
+        assert_eq!(masked.services["indexer"].env["HF"], MASK);
+        assert_eq!(masked.services["indexer"].args, ["--workers=2"]);
+
+        let text = toml::to_string_pretty(&masked).unwrap();
+        assert!(!text.contains("pw@db"));
+        assert!(!text.contains("hf_abc"));
+    }
+}
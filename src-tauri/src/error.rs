@@ -0,0 +1,70 @@
+use std::fmt;
+
+use serde::Serialize;
+
+/// 所有 command 统一的错误类型，序列化为 `{ kind, message? }` 供前端按 kind 分支处理
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", content = "message")]
+pub enum CommandError {
+    SidecarNotRunning,
+    SpawnFailed(String),
+    Timeout,
+    Io(String),
+    /// 应用正在退出，拒绝再操作 sidecar
+    ShuttingDown,
+    /// sidecar 的 stdin 管道已断开，进程多半已退出或正在退出
+    PipeBroken,
+    /// 当前平台不支持该操作，例如 Windows 上发送 Unix 信号
+    Unsupported(String),
+    /// 参数不合法，例如未知的日志级别
+    InvalidArgument(String),
+    /// sidecar 尚未就绪且待发送队列已满
+    QueueFull,
+    /// sidecar 通过 RPC 返回的错误
+    Backend(String),
+    /// sidecar 可执行文件缺失或不可执行，message 包含预期路径
+    BinaryUnusable(String),
+    /// 全局快捷键已被其他程序占用或无法注册
+    ShortcutUnavailable(String),
+    /// 授权检查未通过，处于受限模式，后端不会启动
+    NotActivated(String),
+    /// 崩溃熔断已打开，自动重启被拒绝，等用户显式重试
+    CircuitOpen,
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::SidecarNotRunning => write!(f, "Sidecar is not running"),
+            Self::SpawnFailed(e) => write!(f, "Failed to spawn sidecar: {}", e),
+            Self::Timeout => write!(f, "Timed out waiting for the sidecar"),
+            Self::Io(e) => write!(f, "I/O error: {}", e),
+            Self::ShuttingDown => write!(f, "Application is shutting down"),
+            Self::PipeBroken => write!(f, "Sidecar stdin pipe is broken"),
+            Self::Unsupported(e) => write!(f, "Unsupported: {}", e),
+            Self::InvalidArgument(e) => write!(f, "Invalid argument: {}", e),
+            Self::QueueFull => write!(f, "Sidecar is not ready and the send queue is full"),
+            Self::Backend(e) => write!(f, "Backend error: {}", e),
+            Self::BinaryUnusable(e) => write!(f, "{}", e),
+            Self::ShortcutUnavailable(e) => write!(f, "Shortcut unavailable: {}", e),
+            Self::NotActivated(e) => write!(f, "Not activated: {}", e),
+            Self::CircuitOpen => write!(f, "The backend keeps crashing, waiting for a retry"),
+        }
+    }
+}
+
+impl std::error::Error for CommandError {}
+
+impl From<std::io::Error> for CommandError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e.to_string())
+    }
+}
+
+impl From<tauri::Error> for CommandError {
+    fn from(e: tauri::Error) -> Self {
+        Self::Io(e.to_string())
+    }
+}
+
+pub type CommandResult<T> = Result<T, CommandError>;
@@ -0,0 +1,75 @@
+use tauri::{AppHandle, Url};
+use tauri_plugin_opener::OpenerExt;
+
+use crate::error::{CommandError, CommandResult};
+
+/// open_external 只放行这些 scheme，file://、javascript: 等一律拒绝
+const ALLOWED_SCHEMES: &[&str] = &["http", "https", "mailto"];
+
+/// 校验后用系统默认程序打开外部链接
+#[tauri::command]
+pub fn open_external(app: AppHandle, url: String) -> CommandResult<()> {
+    let url = check_url(&url)?;
+    log::info!("Opening external URL with scheme '{}'", url.scheme());
+    app.opener()
+        .open_url(url.as_str(), None::<&str>)
+        .map_err(|e| CommandError::Io(e.to_string()))
+}
+
+fn check_url(raw: &str) -> CommandResult<Url> {
+    let url = Url::parse(raw.trim())
+        .map_err(|e| CommandError::InvalidArgument(format!("malformed URL: {}", e)))?;
+    if !ALLOWED_SCHEMES.contains(&url.scheme()) {
+        return Err(CommandError::InvalidArgument(format!(
+            "URL scheme '{}' is not allowed",
+            url.scheme()
+        )));
+    }
+    if url.scheme() != "mailto" && url.host_str().map_or(true, str::is_empty) {
+        return Err(CommandError::InvalidArgument("URL has no host".to_string()));
+    }
+    Ok(url)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn web_and_mail_links_are_allowed() {
+        for url in [
+            "https://github.com/Tonwed/NullGravity",
+            "http://127.0.0.1:8046/api/health",
+            "HTTPS://Example.com/path?q=1",
+            "mailto:support@example.com",
+        ] {
+            assert!(check_url(url).is_ok(), "{} should be allowed", url);
+        }
+    }
+
+    #[test]
+    fn other_schemes_are_rejected() {
+        for url in [
+            "file:///etc/passwd",
+            "file://C:/Windows/System32/cmd.exe",
+            "javascript:alert(1)",
+            "data:text/html,<script>alert(1)</script>",
+            "smb://server/share",
+            "ms-settings:privacy",
+            "nullgravity://open",
+        ] {
+            assert!(
+                matches!(check_url(url), Err(CommandError::InvalidArgument(_))),
+                "{} should be rejected",
+                url
+            );
+        }
+    }
+
+    #[test]
+    fn malformed_urls_are_rejected() {
+        for url in ["", "example.com", "/etc/passwd", "https://"] {
+            assert!(check_url(url).is_err(), "{} should be rejected", url);
+        }
+    }
+}
@@ -0,0 +1,63 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::Duration;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, Window, WindowEvent};
+
+use crate::sidecar::SidecarState;
+use crate::RPC_TIMEOUT;
+
+/// 焦点状态稳定这么久才推送，切换窗口时的连续 Focused(false)/Focused(true) 会被合并
+const FOCUS_DEBOUNCE: Duration = Duration::from_millis(250);
+
+/// 主窗口焦点变化推给前端（`window-focus` 事件）和 sidecar（`focus` RPC），由后端自行决定轮询节奏
+#[derive(Default)]
+pub struct FocusState {
+    /// 最近一次推送出去的焦点状态
+    focused: AtomicBool,
+    /// 每次焦点变化递增，延迟推送时用来判断是否又有新变化
+    generation: AtomicU64,
+}
+
+#[derive(Clone, Serialize)]
+struct WindowFocus {
+    focused: bool,
+}
+
+pub fn track(window: &Window, event: &WindowEvent) {
+    let WindowEvent::Focused(focused) = *event else {
+        return;
+    };
+    if window.label() != "main" {
+        return;
+    }
+    let app = window.app_handle().clone();
+    let generation = app
+        .state::<FocusState>()
+        .generation
+        .fetch_add(1, Ordering::SeqCst)
+        + 1;
+
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(FOCUS_DEBOUNCE).await;
+        let state = app.state::<FocusState>();
+        if state.generation.load(Ordering::SeqCst) != generation
+            || state.focused.swap(focused, Ordering::SeqCst) == focused
+        {
+            return;
+        }
+        let _ = app.emit("window-focus", WindowFocus { focused });
+        send(&app, focused).await;
+    });
+}
+
+/// 只告诉已就绪的 sidecar，启动期间的焦点变化不值得排队
+async fn send(app: &AppHandle, focused: bool) {
+    if !app.state::<SidecarState>().is_ready() {
+        return;
+    }
+    let params = serde_json::json!({ "focused": focused });
+    if let Err(e) = crate::rpc::call(app, "focus", params, RPC_TIMEOUT).await {
+        log::debug!("Sidecar focus notification failed: {}", e);
+    }
+}
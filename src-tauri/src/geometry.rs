@@ -0,0 +1,193 @@
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tauri::{
+    AppHandle, Manager, PhysicalPosition, PhysicalSize, WebviewWindow, Window, WindowEvent,
+};
+
+use crate::sidecar::lock_recover;
+
+const GEOMETRY_FILE: &str = "window-state.json";
+/// 连续 move/resize 只在停下来这么久之后落盘一次
+const SAVE_DEBOUNCE: Duration = Duration::from_millis(500);
+/// 窗口至少要有这么多像素落在某个显示器内才算可见
+const MIN_VISIBLE_PX: i32 = 100;
+
+/// 主窗口几何信息（物理像素），最大化时保留还原前的尺寸
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Geometry {
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+    maximized: bool,
+    /// 窗口所在显示器的名字，旧文件里没有
+    #[serde(default)]
+    monitor: Option<String>,
+}
+
+#[derive(Default)]
+pub struct GeometryState {
+    last: Mutex<Option<Geometry>>,
+    /// 每次变化递增，延迟保存时用来判断是否又有新变化
+    generation: AtomicU64,
+}
+
+fn geometry_path(app: &AppHandle) -> Option<PathBuf> {
+    crate::paths::config_dir(app)
+        .ok()
+        .map(|dir| dir.join(GEOMETRY_FILE))
+}
+
+fn load(app: &AppHandle) -> Option<Geometry> {
+    let raw = std::fs::read_to_string(geometry_path(app)?).ok()?;
+    serde_json::from_str(&raw).ok()
+}
+
+fn save(app: &AppHandle) {
+    let state = app.state::<GeometryState>();
+    let Some(geometry) = lock_recover(&state.last, "Window geometry").clone() else {
+        return;
+    };
+    let Some(path) = geometry_path(app) else {
+        return;
+    };
+    if let Some(dir) = path.parent() {
+        let _ = std::fs::create_dir_all(dir);
+    }
+    match serde_json::to_string_pretty(&geometry) {
+        Ok(json) => {
+            if let Err(e) = std::fs::write(&path, json) {
+                log::warn!("Failed to save window geometry: {}", e);
+            }
+        }
+        Err(e) => log::warn!("Failed to serialize window geometry: {}", e),
+    }
+}
+
+/// 在窗口显示前恢复上次的位置和尺寸
+///
+/// 上次所在的显示器已断开时移到主显示器居中，位置不在任何显示器上时保持居中。
+pub fn restore(window: &WebviewWindow) {
+    let app = window.app_handle();
+    let Some(geometry) = load(app) else {
+        return;
+    };
+
+    let _ = window.set_size(PhysicalSize::new(geometry.width, geometry.height));
+    let disconnected = geometry
+        .monitor
+        .as_deref()
+        .is_some_and(|name| !crate::monitor::is_connected(window, name));
+    if disconnected {
+        log::info!(
+            "Monitor {:?} of the saved window position is gone, using the primary monitor",
+            geometry.monitor
+        );
+        crate::monitor::center_on_primary(window);
+    } else if is_on_screen(window, &geometry) {
+        let _ = window.set_position(PhysicalPosition::new(geometry.x, geometry.y));
+    } else {
+        log::info!("Saved window position is off-screen, centering instead");
+        let _ = window.center();
+    }
+    if geometry.maximized {
+        let _ = window.maximize();
+    }
+    *lock_recover(&app.state::<GeometryState>().last, "Window geometry") = Some(geometry);
+}
+
+fn is_on_screen(window: &WebviewWindow, geometry: &Geometry) -> bool {
+    let Ok(monitors) = window.available_monitors() else {
+        return true;
+    };
+    monitors.iter().any(|monitor| {
+        let pos = monitor.position();
+        let size = monitor.size();
+        let right = pos.x + size.width as i32;
+        let bottom = pos.y + size.height as i32;
+        let overlap_x = (geometry.x + geometry.width as i32).min(right) - geometry.x.max(pos.x);
+        let overlap_y = (geometry.y + geometry.height as i32).min(bottom) - geometry.y.max(pos.y);
+        overlap_x >= MIN_VISIBLE_PX && overlap_y >= MIN_VISIBLE_PX
+    })
+}
+
+/// 记录主窗口的 move/resize，延迟保存；关闭时立即保存
+pub fn track(window: &Window, event: &WindowEvent) {
+    if window.label() != "main" {
+        return;
+    }
+    match event {
+        WindowEvent::Moved(_) | WindowEvent::Resized(_) => {
+            if capture(window) {
+                schedule_save(window.app_handle().clone());
+            }
+        }
+        WindowEvent::CloseRequested { .. } => {
+            capture(window);
+            save(window.app_handle());
+        }
+        _ => {}
+    }
+}
+
+fn capture(window: &Window) -> bool {
+    if window.is_minimized().unwrap_or(false) {
+        return false;
+    }
+    let state = window.app_handle().state::<GeometryState>();
+    let mut last = lock_recover(&state.last, "Window geometry");
+    let maximized = window.is_maximized().unwrap_or(false);
+
+    // 最大化时只记标记，保留还原后的尺寸
+    let next = match (maximized, last.clone()) {
+        (true, Some(previous)) => Geometry {
+            maximized: true,
+            ..previous
+        },
+        _ => {
+            let (Ok(pos), Ok(size)) = (window.outer_position(), window.inner_size()) else {
+                return false;
+            };
+            if size.width == 0 || size.height == 0 {
+                return false;
+            }
+            Geometry {
+                x: pos.x,
+                y: pos.y,
+                width: size.width,
+                height: size.height,
+                maximized,
+                monitor: window
+                    .current_monitor()
+                    .ok()
+                    .flatten()
+                    .and_then(|m| m.name().cloned()),
+            }
+        }
+    };
+    *last = Some(next);
+    state.generation.fetch_add(1, Ordering::SeqCst);
+    true
+}
+
+fn schedule_save(app: AppHandle) {
+    let generation = app
+        .state::<GeometryState>()
+        .generation
+        .load(Ordering::SeqCst);
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(SAVE_DEBOUNCE).await;
+        if app
+            .state::<GeometryState>()
+            .generation
+            .load(Ordering::SeqCst)
+            == generation
+        {
+            save(&app);
+        }
+    });
+}
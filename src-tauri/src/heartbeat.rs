@@ -0,0 +1,145 @@
+use std::pin::pin;
+use std::sync::Mutex;
+
+use log::{info, warn};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::Notify;
+
+use crate::sidecar::{self, lock_recover, SidecarState};
+use crate::{HEARTBEAT_INTERVAL, HEARTBEAT_MAX_MISSES, HEARTBEAT_TIMEOUT};
+
+pub const PING: &str = "ping";
+pub const PONG: &str = "pong";
+
+/// stdout 读到 pong 时唤醒等待中的心跳
+#[derive(Default)]
+pub struct HeartbeatState {
+    pong: Notify,
+    /// 回过 pong、确认支持心跳的进程 PID
+    armed: Mutex<Option<u32>>,
+}
+
+impl HeartbeatState {
+    pub fn on_pong(&self) {
+        self.pong.notify_waiters();
+    }
+
+    fn arm(&self, pid: u32) {
+        *lock_recover(&self.armed, "Heartbeat armed") = Some(pid);
+    }
+
+    fn is_armed(&self, pid: u32) -> bool {
+        *lock_recover(&self.armed, "Heartbeat armed") == Some(pid)
+    }
+}
+
+/// 健康检查的判定：没回过 pong 的后端不支持心跳，ping 不通也不能说明它失联
+fn healthy(armed: bool, answered: Option<bool>) -> bool {
+    !armed || answered.unwrap_or(false)
+}
+
+#[derive(Clone, Serialize)]
+struct Unhealthy {
+    pid: u32,
+    missed: u32,
+}
+
+/// 发一次 ping 并等 pong；写不进 stdin 时返回 None
+async fn ping(app: &AppHandle) -> Option<bool> {
+    let heartbeat = app.state::<HeartbeatState>();
+    let mut notified = pin!(heartbeat.pong.notified());
+    notified.as_mut().enable();
+    if sidecar::write_line(&app.state::<SidecarState>(), PING).is_err() {
+        return None;
+    }
+    Some(
+        tokio::time::timeout(HEARTBEAT_TIMEOUT, notified)
+            .await
+            .is_ok(),
+    )
+}
+
+/// 对指定 PID 做一次额外的健康检查，进程已被替换或不支持心跳时视为通过
+pub async fn probe(app: &AppHandle, pid: u32) -> bool {
+    if sidecar::current_pid(app) != Some(pid) {
+        return true;
+    }
+    let armed = app.state::<HeartbeatState>().is_armed(pid);
+    let answered = if armed { ping(app).await } else { None };
+    healthy(armed, answered)
+}
+
+/// 为指定 PID 的 sidecar 启动心跳，进程被替换或应用退出时自动结束
+///
+/// 还没回过 pong 的后端视为不支持心跳，不会因此被重启。
+pub fn start(app: AppHandle, pid: u32) {
+    tauri::async_runtime::spawn(async move {
+        let heartbeat = app.state::<HeartbeatState>();
+        let mut missed = 0;
+        loop {
+            tokio::time::sleep(HEARTBEAT_INTERVAL).await;
+            let state = app.state::<SidecarState>();
+            if state.is_shutting_down() || sidecar::current_pid(&app) != Some(pid) {
+                return;
+            }
+
+            let Some(answered) = ping(&app).await else {
+                return;
+            };
+            if answered {
+                if !heartbeat.is_armed(pid) {
+                    info!("Sidecar answers heartbeat, health checks enabled");
+                    heartbeat.arm(pid);
+                }
+                missed = 0;
+                continue;
+            }
+            if !heartbeat.is_armed(pid) {
+                continue;
+            }
+
+            missed += 1;
+            warn!(
+                "Sidecar missed heartbeat ({}/{})",
+                missed, HEARTBEAT_MAX_MISSES
+            );
+            if missed >= HEARTBEAT_MAX_MISSES {
+                warn!("Sidecar PID={} is unresponsive, restarting", pid);
+                let _ = app.emit("sidecar-unhealthy", Unhealthy { pid, missed });
+                let handle = app.clone();
+                tauri::async_runtime::spawn_blocking(move || {
+                    if let Err(e) = sidecar::restart(&handle) {
+                        log::error!("Restart after failed heartbeat failed: {}", e);
+                    }
+                });
+                return;
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backends_without_heartbeat_pass_the_probe() {
+        let state = HeartbeatState::default();
+        assert!(!state.is_armed(42));
+        assert!(healthy(state.is_armed(42), None));
+        assert!(healthy(state.is_armed(42), Some(false)));
+    }
+
+    #[test]
+    fn armed_backends_must_answer_the_probe() {
+        let state = HeartbeatState::default();
+        state.arm(42);
+        assert!(state.is_armed(42));
+        assert!(healthy(true, Some(true)));
+        assert!(!healthy(true, Some(false)));
+        assert!(!healthy(true, None));
+        // 换上的新进程要重新证明自己支持心跳
+        assert!(!state.is_armed(43));
+    }
+}
@@ -0,0 +1,66 @@
+use std::sync::Mutex;
+
+use tauri::plugin::TauriPlugin;
+use tauri::{AppHandle, State, Wry};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+
+use crate::error::{CommandError, CommandResult};
+
+/// 显示/隐藏主窗口的默认全局快捷键
+pub const DEFAULT_SHORTCUT: &str = "Ctrl+Shift+G";
+
+/// 当前注册的快捷键
+#[derive(Default)]
+pub struct HotkeyState(Mutex<Option<Shortcut>>);
+
+pub fn plugin() -> TauriPlugin<Wry> {
+    tauri_plugin_global_shortcut::Builder::new()
+        .with_handler(|app, _shortcut, event| {
+            if event.state() == ShortcutState::Pressed {
+                crate::window::toggle_main(app);
+            }
+        })
+        .build()
+}
+
+#[tauri::command]
+pub fn set_global_shortcut(
+    app: AppHandle,
+    state: State<HotkeyState>,
+    shortcut: String,
+) -> CommandResult<()> {
+    replace(&app, &state, &shortcut)
+}
+
+/// 注销旧快捷键并注册新的；新组合被占用时恢复旧的
+pub(crate) fn replace(
+    app: &AppHandle,
+    state: &HotkeyState,
+    accelerator: &str,
+) -> CommandResult<()> {
+    let shortcut: Shortcut = accelerator.parse().map_err(|e| {
+        CommandError::InvalidArgument(format!("invalid shortcut '{}': {}", accelerator, e))
+    })?;
+
+    let mut current = state.0.lock().unwrap();
+    let manager = app.global_shortcut();
+    if let Some(old) = *current {
+        if old == shortcut {
+            return Ok(());
+        }
+        let _ = manager.unregister(old);
+    }
+
+    if let Err(e) = manager.register(shortcut) {
+        if let Some(old) = *current {
+            let _ = manager.register(old);
+        }
+        return Err(CommandError::ShortcutUnavailable(format!(
+            "{}: {}",
+            accelerator, e
+        )));
+    }
+    *current = Some(shortcut);
+    log::info!("Global shortcut set to {}", accelerator);
+    Ok(())
+}
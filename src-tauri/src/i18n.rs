@@ -0,0 +1,173 @@
+use std::sync::Mutex;
+
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+use crate::sidecar::lock_recover;
+
+/// 内置翻译覆盖的语言，未知语言回落到英文
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Locale {
+    #[default]
+    En,
+    Zh,
+}
+
+impl Locale {
+    /// 按 BCP 47 主语言子标签匹配，"zh-CN"、"zh_TW.UTF-8" 都算中文
+    pub fn from_tag(tag: &str) -> Self {
+        let primary = tag
+            .split(['-', '_', '.'])
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        match primary.as_str() {
+            "zh" => Self::Zh,
+            _ => Self::En,
+        }
+    }
+
+    fn detect() -> Self {
+        sys_locale::get_locale()
+            .map(|tag| Self::from_tag(&tag))
+            .unwrap_or_default()
+    }
+}
+
+/// 需要翻译的界面文案；带 `{name}` 之类占位符的用 `format` 填充
+#[derive(Debug, Clone, Copy)]
+pub enum Msg {
+    Greeting,
+    StartFailed,
+    MissingBinaryHint,
+    QuitPrompt,
+    Quit,
+    Continue,
+    BackendCrashed,
+    Incompatible,
+    AppCrashed,
+    AppCrashedTitle,
+    CopyDetails,
+    Close,
+    NotActivated,
+    KeepsCrashing,
+    KeepsCrashingOnStartup,
+    NoUsablePort,
+    TrayShow,
+    TrayRestart,
+    TrayQuit,
+    TrayRunning,
+    TrayStopped,
+    SplashStarting,
+    SplashFailed,
+    Retry,
+}
+
+/// 内置翻译表
+pub fn text(locale: Locale, msg: Msg) -> &'static str {
+    match (locale, msg) {
+        (Locale::En, Msg::Greeting) => "Hello, {name}! Welcome to NullGravity.",
+        (Locale::Zh, Msg::Greeting) => "你好，{name}！欢迎使用 NullGravity。",
+        (Locale::En, Msg::StartFailed) => "The NullGravity backend could not be started:",
+        (Locale::Zh, Msg::StartFailed) => "NullGravity 后端无法启动：",
+        (Locale::En, Msg::MissingBinaryHint) => {
+            "Reinstall NullGravity or restore the file; antivirus software may have quarantined it."
+        }
+        (Locale::Zh, Msg::MissingBinaryHint) => {
+            "请重新安装 NullGravity 或恢复该文件，它可能被杀毒软件隔离了。"
+        }
+        (Locale::En, Msg::QuitPrompt) => "Quit the application?",
+        (Locale::Zh, Msg::QuitPrompt) => "是否退出应用？",
+        (Locale::En, Msg::Quit) => "Quit",
+        (Locale::Zh, Msg::Quit) => "退出",
+        (Locale::En, Msg::Continue) => "Continue",
+        (Locale::Zh, Msg::Continue) => "继续",
+        (Locale::En, Msg::BackendCrashed) => "The NullGravity backend crashed ({detail}).",
+        (Locale::Zh, Msg::BackendCrashed) => "NullGravity 后端意外退出（{detail}）。",
+        (Locale::En, Msg::Incompatible) => {
+            "The NullGravity backend (v{backend}) is not compatible with this app (v{shell}).\n\nPlease reinstall NullGravity."
+        }
+        (Locale::Zh, Msg::Incompatible) => {
+            "NullGravity 后端（v{backend}）与当前应用（v{shell}）不兼容。\n\n请重新安装 NullGravity。"
+        }
+        (Locale::En, Msg::AppCrashed) => {
+            "NullGravity hit an unexpected error and needs to close."
+        }
+        (Locale::Zh, Msg::AppCrashed) => "NullGravity 遇到意外错误，需要关闭。",
+        (Locale::En, Msg::AppCrashedTitle) => "NullGravity crashed",
+        (Locale::Zh, Msg::AppCrashedTitle) => "NullGravity 已崩溃",
+        (Locale::En, Msg::CopyDetails) => "Copy details",
+        (Locale::Zh, Msg::CopyDetails) => "复制详情",
+        (Locale::En, Msg::Close) => "Close",
+        (Locale::Zh, Msg::Close) => "关闭",
+        (Locale::En, Msg::NotActivated) => {
+            "NullGravity is not activated ({detail}).\n\nThe app runs in limited mode and the backend will not be started."
+        }
+        (Locale::Zh, Msg::NotActivated) => {
+            "NullGravity 尚未激活（{detail}）。\n\n应用将以受限模式运行，不会启动后端。"
+        }
+        (Locale::En, Msg::KeepsCrashing) => "The backend keeps crashing.",
+        (Locale::Zh, Msg::KeepsCrashing) => "后端反复崩溃。",
+        (Locale::En, Msg::KeepsCrashingOnStartup) => "The backend keeps crashing on startup.",
+        (Locale::Zh, Msg::KeepsCrashingOnStartup) => "后端启动时反复崩溃。",
+        (Locale::En, Msg::NoUsablePort) => "No usable port for the backend.",
+        (Locale::Zh, Msg::NoUsablePort) => "没有可供后端使用的端口。",
+        (Locale::En, Msg::TrayShow) => "Show Window",
+        (Locale::Zh, Msg::TrayShow) => "显示窗口",
+        (Locale::En, Msg::TrayRestart) => "Restart Backend",
+        (Locale::Zh, Msg::TrayRestart) => "重启后端",
+        (Locale::En, Msg::TrayQuit) => "Quit NullGravity",
+        (Locale::Zh, Msg::TrayQuit) => "退出 NullGravity",
+        (Locale::En, Msg::TrayRunning) => "NullGravity — backend running",
+        (Locale::Zh, Msg::TrayRunning) => "NullGravity — 后端运行中",
+        (Locale::En, Msg::TrayStopped) => "NullGravity — backend stopped",
+        (Locale::Zh, Msg::TrayStopped) => "NullGravity — 后端已停止",
+        (Locale::En, Msg::SplashStarting) => "Starting NullGravity…",
+        (Locale::Zh, Msg::SplashStarting) => "正在启动 NullGravity…",
+        (Locale::En, Msg::SplashFailed) => "The backend could not be started.",
+        (Locale::Zh, Msg::SplashFailed) => "后端无法启动。",
+        (Locale::En, Msg::Retry) => "Retry",
+        (Locale::Zh, Msg::Retry) => "重试",
+    }
+}
+
+/// 把 `{key}` 占位符替换为对应的值
+pub fn format(template: &str, args: &[(&str, &str)]) -> String {
+    args.iter()
+        .fold(template.to_string(), |text, (key, value)| {
+            text.replace(&format!("{{{}}}", key), value)
+        })
+}
+
+/// 当前界面语言，启动时取系统语言
+pub struct LocaleState(Mutex<Locale>);
+
+impl Default for LocaleState {
+    fn default() -> Self {
+        let locale = Locale::detect();
+        log::info!("UI locale detected as {:?}", locale);
+        Self(Mutex::new(locale))
+    }
+}
+
+impl LocaleState {
+    pub fn get(&self) -> Locale {
+        *lock_recover(&self.0, "Locale")
+    }
+}
+
+/// 当前语言下的文案
+pub fn tr(app: &AppHandle, msg: Msg) -> &'static str {
+    text(app.state::<LocaleState>().get(), msg)
+}
+
+/// 切换界面语言，返回实际生效的语言（不支持的语言回落到 "en"）；托盘菜单随之重建
+#[tauri::command]
+pub fn set_locale(app: AppHandle, lang: String) -> Locale {
+    let locale = Locale::from_tag(&lang);
+    *lock_recover(&app.state::<LocaleState>().0, "Locale") = locale;
+    log::info!("UI locale set to {:?} (requested '{}')", locale, lang);
+    crate::tray::relocalize(&app);
+    locale
+}
@@ -0,0 +1,65 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::Duration;
+
+use tauri::{AppHandle, Manager, Window, WindowEvent};
+
+use crate::config::SettingsState;
+use crate::RPC_TIMEOUT;
+
+/// 最小化状态稳定这么久之后才通知后端，避免来回切换时反复 pause/resume
+const PAUSE_DEBOUNCE: Duration = Duration::from_secs(1);
+
+/// 主窗口最小化时让后端暂停高开销的后台任务，需在设置中开启 `pause_when_minimized`
+#[derive(Default)]
+pub struct IdleState {
+    /// 已通知后端暂停
+    paused: AtomicBool,
+    /// 每次状态变化递增，延迟发送时用来判断是否又有新变化
+    generation: AtomicU64,
+}
+
+pub fn track(window: &Window, event: &WindowEvent) {
+    if window.label() != "main"
+        || !matches!(event, WindowEvent::Focused(_) | WindowEvent::Resized(_))
+    {
+        return;
+    }
+    let app = window.app_handle();
+    if !app.state::<SettingsState>().get().pause_when_minimized {
+        return;
+    }
+    let minimized = window.is_minimized().unwrap_or(false);
+    let state = app.state::<IdleState>();
+    let generation = state.generation.fetch_add(1, Ordering::SeqCst) + 1;
+    if state.paused.load(Ordering::SeqCst) == minimized {
+        return;
+    }
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(PAUSE_DEBOUNCE).await;
+        let state = app.state::<IdleState>();
+        if state.generation.load(Ordering::SeqCst) != generation {
+            return;
+        }
+        if state.paused.swap(minimized, Ordering::SeqCst) != minimized {
+            send(&app, minimized).await;
+        }
+    });
+}
+
+/// 新启动的 sidecar 默认是运行状态，窗口仍最小化时重新通知暂停
+pub fn on_ready(app: &AppHandle) {
+    if app.state::<IdleState>().paused.load(Ordering::SeqCst) {
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move { send(&app, true).await });
+    }
+}
+
+async fn send(app: &AppHandle, pause: bool) {
+    let method = if pause { "pause" } else { "resume" };
+    log::info!("Window minimized={}, asking sidecar to {}", pause, method);
+    if let Err(e) = crate::rpc::call(app, method, serde_json::Value::Null, RPC_TIMEOUT).await {
+        log::warn!("Sidecar {} request failed: {}", method, e);
+    }
+}
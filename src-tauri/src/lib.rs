@@ -1,66 +1,265 @@
-use std::net::TcpListener;
-use std::sync::Mutex;
+mod activation;
+mod binary;
+mod breaker;
+mod cli;
+mod config;
+mod controller;
+mod crash;
+mod deeplink;
+mod diagnostics;
+mod error;
+mod external;
+mod focus;
+mod geometry;
+mod heartbeat;
+mod hotkey;
+mod i18n;
+mod idle;
+mod lifecycle;
+mod logfile;
+mod logging;
+mod metrics;
+mod monitor;
+mod output;
+mod paths;
+mod port;
+mod process;
+mod redact;
+mod rpc;
+mod services;
+mod session;
+mod shutdown;
+mod sidecar;
+mod splash;
+mod startup;
+mod stream;
+mod task;
+mod theme;
+mod tray;
+mod version;
+mod window;
+
+use std::time::Duration;
 use tauri::Manager;
-use tauri_plugin_shell::ShellExt;
-use tauri_plugin_shell::process::CommandEvent;
 use tauri::utils::config::WindowEffectsConfig;
 use tauri::utils::WindowEffect;
 
-struct SidecarPid(Mutex<Option<u32>>);
+use activation::ActivationState;
+use binary::BinaryInfoState;
+use deeplink::DeepLinkState;
+use diagnostics::DiagnosticsState;
+use focus::FocusState;
+use geometry::GeometryState;
+use heartbeat::HeartbeatState;
+use hotkey::HotkeyState;
+use i18n::{LocaleState, Msg};
+use idle::IdleState;
+use lifecycle::ReadyState;
+use metrics::MetricsState;
+use output::{LogBatcher, LogBuffer, LogSubscribers, LogWindows};
+use paths::AppDirs;
+use redact::Redactor;
+use rpc::RpcState;
+use services::SidecarRegistry;
+use session::SessionLog;
+use shutdown::{QuitGuard, QuitState};
+use sidecar::{ManagedPort, SidecarState};
+use splash::SplashState;
+use startup::StartupTimings;
+use stream::StreamState;
+use version::VersionState;
+use window::WindowState;
+
+/// 商业构建通过它在 `run()` 之前接入授权检查
+pub use activation::{set_activation_check, ActivationCheck, ActivationError};
+
+/// sidecar 连续崩溃后自动重启的最大次数，超过后放弃
+pub(crate) const MAX_RESPAWN_ATTEMPTS: u32 = 5;
+/// 重启退避的起始间隔与上限
+pub(crate) const RESPAWN_BACKOFF_BASE: Duration = Duration::from_millis(500);
+pub(crate) const RESPAWN_BACKOFF_MAX: Duration = Duration::from_secs(30);
+/// 进程平稳运行超过该时长后，连续失败计数清零
+pub(crate) const RESPAWN_RESET_WINDOW: Duration = Duration::from_secs(60);
+/// 熔断：这段时间内崩溃超过这么多次就停止自动重启，等待用户显式重试
+pub(crate) const CIRCUIT_BREAKER_CRASHES: u32 = 5;
+pub(crate) const CIRCUIT_BREAKER_WINDOW: Duration = Duration::from_secs(600);
+/// 关闭时等待 sidecar 自行退出的时长，超时后强杀
+pub(crate) const SHUTDOWN_GRACE: Duration = Duration::from_secs(5);
+/// 强杀后再等这么久仍未退出，就报告进程卡死
+pub(crate) const SHUTDOWN_HARD_TIMEOUT: Duration = Duration::from_secs(2);
+/// 报告卡死后在后台继续强杀的次数和间隔
+pub(crate) const STUCK_KILL_RETRIES: u32 = 10;
+pub(crate) const STUCK_KILL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 首次启动 sidecar 的尝试次数，第 n 次失败后等待 n * STARTUP_RETRY_DELAY
+pub(crate) const STARTUP_SPAWN_ATTEMPTS: u32 = 5;
+pub(crate) const STARTUP_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// 启动时清理上次崩溃遗留的 sidecar 进程
+pub(crate) const CLEANUP_ORPHANS: bool = true;
+
+/// sidecar 在 stdout 打印这一行表示已可以接受连接
+pub(crate) const READY_SENTINEL: &str = "NULLGRAVITY_READY";
+/// 等不到就绪信号时，最多等这么久就先把窗口显示出来
+pub(crate) const READY_TIMEOUT: Duration = Duration::from_secs(15);
+/// PortProbe 模式下尝试连接 sidecar 端口的间隔
+pub(crate) const READY_PROBE_INTERVAL: Duration = Duration::from_millis(200);
+/// 外部后端就绪后的连通性探测间隔
+pub(crate) const EXTERNAL_PROBE_INTERVAL: Duration = Duration::from_secs(2);
+
+/// 单次 sidecar_call 等待响应的上限
+pub(crate) const RPC_TIMEOUT: Duration = Duration::from_secs(10);
+/// sidecar 就绪前最多缓存这么多条前端发来的消息，超出后直接报错
+pub(crate) const OUTBOUND_QUEUE_LIMIT: usize = 256;
+/// 发现 stdin 管道断开后等这么久再检查进程死活，让 watchdog 先处理正常的退出
+pub(crate) const PIPE_CHECK_DELAY: Duration = Duration::from_millis(500);
+/// 进程仍在但连续这么多次调用超时，判定为后端失联
+pub(crate) const RPC_DEGRADED_AFTER: u32 = 3;
+
+/// 心跳间隔、等待 pong 的超时，以及连续多少次未响应后判定为假死
+pub(crate) const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
+pub(crate) const HEARTBEAT_TIMEOUT: Duration = Duration::from_secs(2);
+pub(crate) const HEARTBEAT_MAX_MISSES: u32 = 3;
+
+/// greet 等后端回应的时间，超时即使用本地文案
+pub(crate) const GREET_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// 探测到的端口启动前被占用时，最多重新挑选的次数
+pub(crate) const PORT_RETRY_LIMIT: u32 = 5;
+
+/// 检查 sidecar 可执行文件是否被替换的间隔
+pub(crate) const BINARY_WATCH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// 以无界面的后台服务方式运行：不创建窗口，只监管 sidecar，通过托盘或信号退出
+pub(crate) const HEADLESS_FLAG: &str = "--headless";
+pub(crate) const HEADLESS_ENV: &str = "NULLGRAVITY_HEADLESS";
 
-/// 找一个系统可用的空闲端口
-fn find_free_port() -> u16 {
-    TcpListener::bind("127.0.0.1:0")
-        .expect("Failed to bind to find free port")
-        .local_addr()
-        .unwrap()
-        .port()
+/// `--version` 时最多等 sidecar 报告版本这么久，超时只打印外壳版本
+pub(crate) const CLI_VERSION_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// 把配置、日志和后端数据都放到指定目录下，便于并行运行多个互相隔离的实例
+pub(crate) const DATA_DIR_FLAG: &str = "--data-dir";
+
+/// 开发时连接自己另行启动的后端：不拉起 sidecar，前端和状态查询直接使用这个端口
+pub(crate) const EXTERNAL_SIDECAR_FLAG: &str = "--external-sidecar";
+pub(crate) const EXTERNAL_SIDECAR_ENV: &str = "NULLGRAVITY_EXTERNAL_SIDECAR";
+
+/// 命令行 `<flag> <value>` 或 `<flag>=<value>` 的值
+fn flag_value(flag: &str) -> Option<String> {
+    let mut args = std::env::args().skip(1);
+    let prefix = format!("{}=", flag);
+    loop {
+        let arg = args.next()?;
+        if arg == flag {
+            return args.next();
+        }
+        if let Some(value) = arg.strip_prefix(&prefix) {
+            return Some(value.to_string());
+        }
+    }
+}
+
+/// 命令行 `--external-sidecar <port>`，其次环境变量 NULLGRAVITY_EXTERNAL_SIDECAR
+fn external_sidecar_requested() -> Option<u16> {
+    let raw = flag_value(EXTERNAL_SIDECAR_FLAG)
+        .or_else(|| std::env::var(EXTERNAL_SIDECAR_ENV).ok())
+        .filter(|v| !v.trim().is_empty())?;
+    match raw.trim().parse() {
+        Ok(port) => Some(port),
+        Err(_) => {
+            eprintln!("Ignoring invalid external sidecar port '{}'", raw);
+            None
+        }
+    }
 }
 
-/// 非阻塞 kill，不弹黑窗口
-fn kill_by_pid_nowait(pid: u32) {
-    #[cfg(target_os = "windows")]
-    {
-        use std::os::windows::process::CommandExt;
-        const CREATE_NO_WINDOW: u32 = 0x08000000;
-        let _ = std::process::Command::new("taskkill")
-            .args(["/F", "/T", "/PID", &pid.to_string()])
-            .creation_flags(CREATE_NO_WINDOW)
-            .spawn();
+/// 命令行 `--data-dir <path>` 或 `--data-dir=<path>`，相对路径按当前目录解析
+fn data_dir_requested() -> Option<std::path::PathBuf> {
+    let path = std::path::PathBuf::from(flag_value(DATA_DIR_FLAG)?);
+    if path.is_absolute() {
+        return Some(path);
     }
-    #[cfg(not(target_os = "windows"))]
-    {
-        let _ = std::process::Command::new("kill")
-            .args(["-9", &pid.to_string()])
-            .spawn();
+    std::env::current_dir().ok().map(|cwd| cwd.join(path))
+}
+
+/// 命令行带 `--headless`，或环境变量 NULLGRAVITY_HEADLESS 为非空且不是 0/false
+pub(crate) fn headless_requested() -> bool {
+    std::env::args().any(|arg| arg == HEADLESS_FLAG)
+        || std::env::var(HEADLESS_ENV)
+            .is_ok_and(|v| !v.is_empty() && v != "0" && !v.eq_ignore_ascii_case("false"))
+}
+
+/// 显示并聚焦主窗口
+pub(crate) fn show_main_window(app: &tauri::AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.show();
+        let _ = window.set_focus();
     }
 }
 
+#[derive(serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+enum GreetingSource {
+    Backend,
+    Local,
+}
+
+#[derive(serde::Serialize)]
+struct Greeting {
+    message: String,
+    source: GreetingSource,
+}
+
+/// 通过 RPC 让后端按当前语言生成问候语，后端不可用时退回本地文案
 #[tauri::command]
-fn greet(name: &str) -> String {
-    format!("Hello, {}! Welcome to NullGravity.", name)
+async fn greet(app: tauri::AppHandle, name: String) -> Greeting {
+    let locale = app.state::<LocaleState>().get();
+    let params = serde_json::json!({ "name": name, "locale": locale });
+    greeting_from(
+        name,
+        locale,
+        rpc::call(&app, "greet", params, GREET_TIMEOUT),
+    )
+    .await
 }
 
-#[cfg_attr(mobile, tauri::mobile_entry_point)]
-pub fn run() {
-    let port = find_free_port();
-    eprintln!("[NullGravity] Using backend port: {}", port);
+/// greet 的主体，不依赖 Tauri 运行时，便于测试
+async fn greeting_from(
+    name: String,
+    locale: i18n::Locale,
+    reply: impl std::future::Future<Output = error::CommandResult<serde_json::Value>>,
+) -> Greeting {
+    match reply.await {
+        Ok(serde_json::Value::String(message)) => Greeting {
+            message,
+            source: GreetingSource::Backend,
+        },
+        result => {
+            if let Err(e) = result {
+                log::debug!("greet falling back to local message: {}", e);
+            }
+            Greeting {
+                message: i18n::format(i18n::text(locale, Msg::Greeting), &[("name", &name)]),
+                source: GreetingSource::Local,
+            }
+        }
+    }
+}
 
-    let init_script = format!("window.__BACKEND_PORT__ = {};", port);
+/// 创建启动画面和隐藏的主窗口，主窗口注入端口初始化脚本并恢复 Mica 效果
+///
+/// 主题在创建时就设置好，避免显示后再切换造成闪烁。
+fn open_windows(
+    app: &tauri::App,
+    init_script: &str,
+    theme: Option<tauri::Theme>,
+) -> tauri::Result<()> {
+    splash::open(app.handle(), theme)?;
 
-    tauri::Builder::default()
-        .plugin(tauri_plugin_shell::init())
-        .plugin(tauri_plugin_http::init())
-        .plugin(tauri_plugin_opener::init())
-        .manage(SidecarPid(Mutex::new(None)))
-        .setup(move |app| {
-            // 手动创建窗口，注入端口初始化脚本，恢复 Mica 效果
-            tauri::WebviewWindowBuilder::new(
-                app,
-                "main",
-                tauri::WebviewUrl::App("index.html".into()),
-            )
+    let main_window =
+        tauri::WebviewWindowBuilder::new(app, "main", tauri::WebviewUrl::App("index.html".into()))
             .title("NullGravity")
+            .theme(theme)
             .inner_size(1280.0, 800.0)
             .min_inner_size(900.0, 600.0)
             .center()
@@ -72,78 +271,330 @@ pub fn run() {
                 radius: None,
                 color: None,
             })
-            .initialization_script(&init_script)
-            .build()
-            .expect("Failed to create main window");
+            .initialization_script(init_script)
+            .visible(false)
+            .build()?;
+    geometry::restore(&main_window);
+    Ok(())
+}
 
-            let handle = app.handle().clone();
+#[cfg_attr(mobile, tauri::mobile_entry_point)]
+pub fn run() {
+    // 只打印信息就退出，不创建日志文件、窗口或 sidecar
+    if cli::handle_info_flags() {
+        return;
+    }
+    let started = std::time::Instant::now();
+    crash::install_panic_hook();
 
-            // 通过环境变量把端口传给 sidecar
-            match app.shell().sidecar("nullgravity-core") {
-                Ok(sidecar) => {
-                    match sidecar
-                        .env("NULLGRAVITY_PORT", port.to_string())
-                        .spawn()
-                    {
-                        Ok((mut rx, child)) => {
-                            let pid = child.pid();
-                            *handle.state::<SidecarPid>().0.lock().unwrap() = Some(pid);
-                            eprintln!("[NullGravity] Backend sidecar started, PID={}, PORT={}", pid, port);
-
-                            Box::leak(Box::new(child));
-
-                            tauri::async_runtime::spawn(async move {
-                                while let Some(event) = rx.recv().await {
-                                    match event {
-                                        CommandEvent::Stdout(line) => {
-                                            eprintln!("[core] {}", String::from_utf8_lossy(&line));
-                                        }
-                                        CommandEvent::Stderr(line) => {
-                                            eprintln!("[core:err] {}", String::from_utf8_lossy(&line));
-                                        }
-                                        CommandEvent::Terminated(status) => {
-                                            eprintln!("[core] terminated: {:?}", status);
-                                            break;
-                                        }
-                                        _ => {}
-                                    }
-                                }
-                            });
-                        }
-                        Err(e) => {
-                            eprintln!("[NullGravity] Failed to spawn sidecar: {}", e);
-                        }
-                    }
+    let headless = headless_requested();
+    let data_dir = data_dir_requested();
+    // 指定了独立数据目录的实例彼此隔离，不参与单实例检测
+    let single_instance = data_dir.is_none();
+    let external = external_sidecar_requested();
+    let port = match external {
+        Some(port) => port,
+        None => port::pick_free_port().expect("Failed to bind to find free port"),
+    };
+    let init_script = format!("window.__BACKEND_PORT__ = {};", port);
+
+    let mut builder = tauri::Builder::default();
+    if single_instance {
+        // 必须最先注册：重复启动时在任何 sidecar 拉起之前就退出
+        builder = builder.plugin(tauri_plugin_single_instance::init(|app, argv, _cwd| {
+            log::info!("Rejected duplicate launch, args={:?}", argv);
+            if let Some(window) = app.get_webview_window("main") {
+                let _ = window.unminimize();
+            }
+            show_main_window(app);
+        }));
+    }
+    builder
+        .plugin(tauri_plugin_deep_link::init())
+        .plugin(logging::plugin())
+        // shell 插件只用于解析 externalBin 路径；前端只被授权执行打包的 sidecar（见 sidecar::shell_capability），
+        // Rust 侧启动前同样会检查 bin_name 是否属于 externalBin
+        .plugin(tauri_plugin_shell::init())
+        .plugin(tauri_plugin_http::init())
+        .plugin(tauri_plugin_opener::init())
+        .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_clipboard_manager::init())
+        .plugin(hotkey::plugin())
+        .manage(AppDirs::new(data_dir))
+        .manage(SidecarState::default())
+        .manage(ActivationState::default())
+        .manage(SidecarRegistry::default())
+        .manage(QuitState::default())
+        .manage(QuitGuard::default())
+        .manage(ManagedPort::new(port))
+        .manage(RpcState::default())
+        .manage(HeartbeatState::default())
+        .manage(LogBuffer::default())
+        .manage(LogBatcher::default())
+        .manage(LogSubscribers::default())
+        .manage(LogWindows::default())
+        .manage(SessionLog::default())
+        .manage(Redactor::default())
+        .manage(WindowState::default())
+        .manage(GeometryState::default())
+        .manage(HotkeyState::default())
+        .manage(MetricsState::default())
+        .manage(SplashState::default())
+        .manage(StreamState::default())
+        .manage(VersionState::default())
+        .manage(DiagnosticsState::default())
+        .manage(DeepLinkState::default())
+        .manage(IdleState::default())
+        .manage(LocaleState::default())
+        .manage(BinaryInfoState::default())
+        .manage(FocusState::default())
+        .manage(ReadyState::default())
+        .manage(StartupTimings::new(started, headless))
+        .setup(move |app| {
+            startup::mark(app.handle(), "plugins");
+            app.add_capability(sidecar::shell_capability())?;
+            logging::open_file(app.handle());
+            crash::attach(app.handle());
+            shutdown::install_signal_handler(app.handle());
+            log::info!("Using backend port: {}", port);
+            let settings = config::Settings::load(app.handle());
+            if headless {
+                log::info!("Running headless, no window will be created");
+            } else {
+                open_windows(app, &init_script, settings.theme.window_theme())?;
+            }
+
+            tray::init(app.handle(), headless)?;
+            // 启动时应用失败（比如快捷键被占用）只记录警告
+            for (key, result) in settings.apply(app.handle()) {
+                if let Err(e) = result {
+                    log::warn!("Failed to apply setting '{}': {}", key, e);
+                }
+            }
+
+            if CLEANUP_ORPHANS && external.is_none() {
+                sidecar::cleanup_orphans(&settings.sidecar.bin_name);
+            }
+            app.manage(config::SettingsState::new(settings));
+            session::start(app.handle());
+            match external {
+                Some(port) => sidecar::attach_external(app.handle().clone(), port),
+                None if activation::verify(app.handle()) => {
+                    sidecar::spawn_with_retry(app.handle().clone());
+                    binary::watch(app.handle().clone());
                 }
-                Err(e) => {
-                    eprintln!("[NullGravity] Failed to find sidecar: {:?}", e);
+                // 受限模式：不启动后端，界面直接显示，不等就绪
+                None => {
+                    activation::notify_limited(app.handle());
+                    if !headless {
+                        window::reveal_once(app.handle());
+                    }
                 }
             }
+            deeplink::init(app.handle());
+            if headless {
+                return Ok(());
+            }
+
+            // 后端迟迟不就绪也不能让窗口一直藏着
+            let handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                tokio::time::sleep(READY_TIMEOUT).await;
+                // 启动失败时启动画面在等用户选择，不要替用户关掉
+                if !handle.state::<SidecarState>().is_ready() && !splash::has_failed(&handle) {
+                    log::warn!(
+                        "Sidecar not ready after {:?}, showing window anyway",
+                        READY_TIMEOUT
+                    );
+                    window::reveal_once(&handle);
+                }
+            });
 
             Ok(())
         })
         .on_window_event(|window, event| {
-            if let tauri::WindowEvent::CloseRequested { .. } = event {
-                let app = window.app_handle();
-                if let Ok(mut guard) = app.state::<SidecarPid>().0.lock() {
-                    if let Some(pid) = guard.take() {
-                        eprintln!("[NullGravity] Killing sidecar PID={}", pid);
-                        kill_by_pid_nowait(pid);
-                    }
-                }
+            geometry::track(window, event);
+            idle::track(window, event);
+            focus::track(window, event);
+            theme::track(window, event);
+            output::track(window, event);
+            window::handle_close_requested(window, event);
+            // 只有主窗口销毁才意味着退出，启动画面关闭不算
+            if matches!(event, tauri::WindowEvent::Destroyed) && window.label() == "main" {
+                let outcome = sidecar::shutdown_sidecar(window.app_handle());
+                log::info!("Sidecar shutdown on window destroy: {:?}", outcome);
             }
         })
-        .invoke_handler(tauri::generate_handler![greet])
+        .invoke_handler(tauri::generate_handler![
+            greet,
+            sidecar::sidecar_status,
+            sidecar::sidecar_launch_info,
+            sidecar::last_spawn_error,
+            lifecycle::wait_for_ready,
+            sidecar::restart_sidecar,
+            sidecar::retry_start_sidecar,
+            sidecar::sidecar_port,
+            sidecar::sidecar_send,
+            sidecar::send_signal,
+            version::sidecar_version,
+            rpc::sidecar_call,
+            rpc::rpc_latency_stats,
+            services::list_services,
+            services::start_service,
+            services::stop_service,
+            stream::start_stream,
+            stream::stream_ack,
+            output::recent_logs,
+            output::subscribe_log_batches,
+            output::unsubscribe_log_batches,
+            session::previous_session_log,
+            output::subscribe_logs,
+            output::unsubscribe_logs,
+            metrics::sidecar_metrics,
+            binary::sidecar_binary_info,
+            diagnostics::diagnostics,
+            diagnostics::export_diagnostics,
+            diagnostics::sidecar_dump,
+            external::open_external,
+            config::get_settings,
+            config::save_settings,
+            config::reload_settings,
+            config::open_config_dir,
+            logging::log_file_path,
+            logging::open_log_dir,
+            logging::rotate_log,
+            logging::read_log_tail,
+            logging::set_log_level,
+            window::set_close_behavior,
+            window::set_window_title,
+            theme::set_theme,
+            theme::get_theme,
+            monitor::list_monitors,
+            monitor::move_window_to_monitor,
+            hotkey::set_global_shortcut,
+            i18n::set_locale,
+            shutdown::quit_app,
+            shutdown::set_quit_guard,
+            shutdown::cancel_quit,
+            activation::activation_status,
+            activation::retry_activation,
+            splash::splash_retry,
+            splash::splash_quit,
+        ])
         .build(tauri::generate_context!())
         .expect("error while building NullGravity")
         .run(|app, event| {
             if let tauri::RunEvent::Exit = event {
-                if let Ok(mut guard) = app.state::<SidecarPid>().0.lock() {
-                    if let Some(pid) = guard.take() {
-                        eprintln!("[NullGravity] Exit: Killing sidecar PID={}", pid);
-                        kill_by_pid_nowait(pid);
-                    }
-                }
+                session::flush(app);
+                let outcome = sidecar::shutdown_sidecar(app);
+                log::info!("Sidecar shutdown on exit: {:?}", outcome);
+                sidecar::join_reapers(app);
             }
         });
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::*;
+
+    /// 单线程运行时充当 UI 事件循环，每 10ms tick 一次，返回 tick 之间的最大间隔
+    async fn event_loop_ticks(count: u32) -> Duration {
+        let mut worst = Duration::ZERO;
+        let mut last = Instant::now();
+        for _ in 0..count {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            let now = Instant::now();
+            worst = worst.max(now - last);
+            last = now;
+        }
+        worst
+    }
+
+    fn ui_runtime() -> tokio::runtime::Runtime {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn concurrent_greets_do_not_starve_event_loop() {
+        ui_runtime().block_on(async {
+            let ticker = tokio::spawn(event_loop_ticks(20));
+            let greets: Vec<_> = (0..200)
+                .map(|i| {
+                    tokio::spawn(greeting_from(
+                        format!("user{}", i),
+                        i18n::Locale::En,
+                        async {
+                            tokio::time::sleep(Duration::from_millis(100)).await;
+                            Ok(serde_json::Value::String("hi".into()))
+                        },
+                    ))
+                })
+                .collect();
+
+            for greet in greets {
+                let greeting = greet.await.unwrap();
+                assert!(matches!(greeting.source, GreetingSource::Backend));
+            }
+            let worst = ticker.await.unwrap();
+            assert!(
+                worst < Duration::from_millis(50),
+                "event loop stalled for {:?}",
+                worst
+            );
+        });
+    }
+
+    #[test]
+    fn greet_falls_back_when_backend_fails() {
+        ui_runtime().block_on(async {
+            let greeting = greeting_from("Ada".into(), i18n::Locale::En, async {
+                Err(error::CommandError::SidecarNotRunning)
+            })
+            .await;
+            assert!(matches!(greeting.source, GreetingSource::Local));
+            assert!(greeting.message.contains("Ada"));
+        });
+    }
+
+    #[test]
+    fn local_greeting_follows_locale() {
+        ui_runtime().block_on(async {
+            let greeting = greeting_from("Ada".into(), i18n::Locale::from_tag("zh-CN"), async {
+                Err(error::CommandError::SidecarNotRunning)
+            })
+            .await;
+            assert_eq!(greeting.message, "你好，Ada！欢迎使用 NullGravity。");
+            assert_eq!(i18n::Locale::from_tag("fr-FR"), i18n::Locale::En);
+        });
+    }
+
+    #[test]
+    fn blocking_work_runs_off_the_event_loop() {
+        ui_runtime().block_on(async {
+            let ticker = tokio::spawn(event_loop_ticks(20));
+            let jobs: Vec<_> = (0..32)
+                .map(|_| {
+                    tokio::spawn(task::blocking(|| {
+                        std::thread::sleep(Duration::from_millis(100));
+                        Ok(())
+                    }))
+                })
+                .collect();
+
+            for job in jobs {
+                job.await.unwrap().unwrap();
+            }
+            let worst = ticker.await.unwrap();
+            assert!(
+                worst < Duration::from_millis(50),
+                "event loop stalled for {:?}",
+                worst
+            );
+        });
+    }
+}
@@ -0,0 +1,102 @@
+use std::time::Duration;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tokio::sync::watch;
+
+use crate::diagnostics::DiagnosticsState;
+use crate::error::{CommandError, CommandResult};
+use crate::logging::LogLevelSync;
+
+/// sidecar 状态迁移，统一通过 `sidecar-lifecycle` 事件推给前端
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum SidecarEvent {
+    Starting,
+    Ready,
+    Crashed {
+        code: Option<i32>,
+    },
+    Restarting {
+        attempt: u32,
+    },
+    /// 端口冲突后换用了新端口，前端需按新端口重连
+    PortChanged {
+        port: u16,
+    },
+    /// `window_secs` 秒内崩溃超过 `crashes` 次，已停止自动重启，需调用 retry_start_sidecar
+    CircuitOpen {
+        crashes: u32,
+        window_secs: u64,
+    },
+    /// 进程还在，但连续 `timeouts` 次调用没有响应，UI 可显示“重连中”；恢复后会再发 Ready
+    Degraded {
+        timeouts: u32,
+    },
+    /// stdin 管道已断开，前端应停止发送，直到下一次 Ready
+    PipeBroken,
+    /// 关闭时强杀后进程仍未退出，后台会继续重试强杀
+    ShutdownStuck {
+        pid: u32,
+    },
+    /// 检测到 sidecar 可执行文件被替换，即将重启
+    Updated,
+    /// 日志级别已调整，`sync` 表示同步给 sidecar 的方式
+    LogLevel {
+        level: String,
+        sync: LogLevelSync,
+    },
+    Stopped,
+}
+
+/// 随生命周期事件更新的就绪状态，供 wait_for_ready 等待
+pub struct ReadyState(watch::Sender<bool>);
+
+impl Default for ReadyState {
+    fn default() -> Self {
+        Self(watch::channel(false).0)
+    }
+}
+
+impl ReadyState {
+    fn observe(&self, event: &SidecarEvent) {
+        let ready = match event {
+            SidecarEvent::Ready => true,
+            SidecarEvent::Starting
+            | SidecarEvent::Crashed { .. }
+            | SidecarEvent::Restarting { .. }
+            | SidecarEvent::CircuitOpen { .. }
+            | SidecarEvent::Degraded { .. }
+            | SidecarEvent::PipeBroken
+            | SidecarEvent::ShutdownStuck { .. }
+            | SidecarEvent::Stopped => false,
+            SidecarEvent::PortChanged { .. }
+            | SidecarEvent::Updated
+            | SidecarEvent::LogLevel { .. } => return,
+        };
+        self.0.send_replace(ready);
+    }
+
+    /// 等到 Ready，已就绪时立即返回；超过 `timeout` 返回 Timeout
+    pub async fn wait(&self, timeout: Duration) -> CommandResult<()> {
+        let mut ready = self.0.subscribe();
+        match tokio::time::timeout(timeout, ready.wait_for(|r| *r)).await {
+            Ok(Ok(_)) => Ok(()),
+            Ok(Err(_)) => Err(CommandError::SidecarNotRunning),
+            Err(_) => Err(CommandError::Timeout),
+        }
+    }
+}
+
+pub fn emit(app: &AppHandle, event: SidecarEvent) {
+    log::debug!("Sidecar lifecycle: {:?}", event);
+    app.state::<DiagnosticsState>().observe(&event);
+    app.state::<ReadyState>().observe(&event);
+    let _ = app.emit("sidecar-lifecycle", event);
+}
+
+/// 等到 sidecar 进入 Ready，已就绪时立即返回；超过 `timeout_ms` 返回 Timeout
+#[tauri::command]
+pub async fn wait_for_ready(state: State<'_, ReadyState>, timeout_ms: u64) -> CommandResult<()> {
+    state.wait(Duration::from_millis(timeout_ms)).await
+}
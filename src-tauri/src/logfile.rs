@@ -0,0 +1,256 @@
+//! 自己管理日志文件，而不是交给 tauri-plugin-log 的 LogDir 目标，这样可以按需轮转
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+
+use time::macros::format_description;
+use time::OffsetDateTime;
+
+/// 文件打开前（setup 之前）产生的日志最多先缓存这么多字节
+const PENDING_MAX_BYTES: usize = 1024 * 1024;
+
+struct Sink {
+    /// 当前日志文件的路径，nullgravity.log
+    path: Option<PathBuf>,
+    file: Option<File>,
+    written: u64,
+    pending: Vec<u8>,
+    max_bytes: u64,
+    keep: usize,
+}
+
+static SINK: Mutex<Sink> = Mutex::new(Sink {
+    path: None,
+    file: None,
+    written: 0,
+    pending: Vec::new(),
+    max_bytes: u64::MAX,
+    keep: 0,
+});
+
+fn sink() -> MutexGuard<'static, Sink> {
+    SINK.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// 交给 fern 的写入端，所有实例共用同一个文件
+pub struct FileSink;
+
+impl Write for FileSink {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        sink().write(buf)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        sink().flush()
+    }
+}
+
+/// 打开 `path` 并写入此前缓存的日志；超过 `max_bytes` 后轮转，最多保留 `keep` 个旧文件
+pub fn open(path: PathBuf, max_bytes: u64, keep: usize) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let file = OpenOptions::new().create(true).append(true).open(&path)?;
+    let mut sink = sink();
+    sink.written = file.metadata().map(|m| m.len()).unwrap_or(0);
+    sink.file = Some(file);
+    sink.path = Some(path);
+    sink.max_bytes = max_bytes;
+    sink.keep = keep;
+    let pending = std::mem::take(&mut sink.pending);
+    sink.write(&pending)
+}
+
+/// 刷新并立即轮转，返回刚关闭的文件路径
+pub fn rotate() -> io::Result<PathBuf> {
+    let mut sink = sink();
+    sink.flush()?;
+    sink.rotate()
+}
+
+impl Sink {
+    fn write(&mut self, buf: &[u8]) -> io::Result<()> {
+        let Some(file) = self.file.as_mut() else {
+            if self.pending.len() + buf.len() <= PENDING_MAX_BYTES {
+                self.pending.extend_from_slice(buf);
+            }
+            return Ok(());
+        };
+        file.write_all(buf)?;
+        self.written += buf.len() as u64;
+        // fern 会把一条记录分几次写入，只在行尾轮转，避免一行被拆到两个文件
+        if self.written >= self.max_bytes && buf.ends_with(b"\n") {
+            self.rotate()?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self.file.as_mut() {
+            Some(file) => file.flush(),
+            None => Ok(()),
+        }
+    }
+
+    /// 当前文件改名为 `<name>_<时间>.log` 后重新打开，超出保留数的旧文件删除
+    fn rotate(&mut self) -> io::Result<PathBuf> {
+        let Some(path) = self.path.clone() else {
+            return Err(io::Error::other("log file is not open yet"));
+        };
+        // Windows 不能重命名打开着的文件，先关掉
+        self.file = None;
+        let archived = archive_path(&path);
+        let renamed = std::fs::rename(&path, &archived);
+        self.file = Some(OpenOptions::new().create(true).append(true).open(&path)?);
+        self.written = 0;
+        renamed?;
+        prune(&path, self.keep);
+        Ok(archived)
+    }
+}
+
+fn archive_path(path: &Path) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let stamp = OffsetDateTime::now_utc()
+        .format(format_description!(
+            "[year]-[month]-[day]_[hour]-[minute]-[second]"
+        ))
+        .unwrap_or_default();
+    let mut archived = path.with_file_name(format!("{}_{}.log", stem, stamp));
+    // 同一秒内轮转多次时追加序号
+    let mut n = 1;
+    while archived.exists() {
+        archived = path.with_file_name(format!("{}_{}.{}.log", stem, stamp, n));
+        n += 1;
+    }
+    archived
+}
+
+/// 旧文件按时间戳排序，同一秒内的按序号排；不带序号的是这一秒里最早的
+fn archive_key(path: &Path) -> (String, u32) {
+    let name = path
+        .file_name()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let name = name.strip_suffix(".log").unwrap_or(&name);
+    match name.rsplit_once('.') {
+        Some((stamp, n)) => match n.parse() {
+            Ok(n) => (stamp.to_string(), n),
+            Err(_) => (name.to_string(), 0),
+        },
+        None => (name.to_string(), 0),
+    }
+}
+
+fn prune(path: &Path, keep: usize) {
+    let (Some(dir), Some(stem)) = (path.parent(), path.file_stem()) else {
+        return;
+    };
+    let prefix = format!("{}_", stem.to_string_lossy());
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let mut archives: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|p| {
+            p.file_name()
+                .is_some_and(|name| name.to_string_lossy().starts_with(&prefix))
+        })
+        .collect();
+    archives.sort_by_key(|p| archive_key(p));
+    let excess = archives.len().saturating_sub(keep);
+    for old in &archives[..excess] {
+        let _ = std::fs::remove_file(old);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_second_archives_sort_after_the_first() {
+        let dir = Path::new("logs");
+        let mut names = vec![
+            dir.join("app_2026-01-01_00-00-01.log"),
+            dir.join("app_2026-01-01_00-00-00.2.log"),
+            dir.join("app_2026-01-01_00-00-00.log"),
+            dir.join("app_2026-01-01_00-00-00.10.log"),
+            dir.join("app_2026-01-01_00-00-00.1.log"),
+        ];
+        names.sort_by_key(|p| archive_key(p));
+        let names: Vec<_> = names
+            .iter()
+            .map(|p| p.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "app_2026-01-01_00-00-00.log",
+                "app_2026-01-01_00-00-00.1.log",
+                "app_2026-01-01_00-00-00.2.log",
+                "app_2026-01-01_00-00-00.10.log",
+                "app_2026-01-01_00-00-01.log",
+            ]
+        );
+    }
+
+    #[test]
+    fn rotation_archives_full_files_and_keeps_the_newest() {
+        let dir = std::env::temp_dir().join(format!("nullgravity-rotate-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("app.log");
+        let mut sink = Sink {
+            path: Some(path.clone()),
+            file: Some(
+                OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&path)
+                    .unwrap(),
+            ),
+            written: 0,
+            pending: Vec::new(),
+            max_bytes: 10,
+            keep: 2,
+        };
+
+        // 一条记录分两次写入，没到行尾不轮转
+        sink.write(b"line-0 ").unwrap();
+        sink.write(b"tail\n").unwrap();
+        for i in 1..4 {
+            sink.write(format!("line-{} tail\n", i).as_bytes()).unwrap();
+        }
+        sink.flush().unwrap();
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "");
+        let mut archives: Vec<PathBuf> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|p| p != &path)
+            .collect();
+        archives.sort_by_key(|p| archive_key(p));
+        assert_eq!(archives.len(), 2);
+        for archive in &archives {
+            let name = archive.file_name().unwrap().to_string_lossy();
+            assert!(
+                name.starts_with("app_") && name.ends_with(".log"),
+                "{}",
+                name
+            );
+        }
+        let contents: Vec<_> = archives
+            .iter()
+            .map(|p| std::fs::read_to_string(p).unwrap())
+            .collect();
+        assert_eq!(contents, ["line-2 tail\n", "line-3 tail\n"]);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,486 @@
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use log::{Level, LevelFilter, Record};
+use serde::{Deserialize, Serialize};
+use tauri::plugin::TauriPlugin;
+use tauri::{AppHandle, Manager, Runtime};
+use tauri_plugin_log::{fern, Target, TargetKind};
+use time::format_description::well_known::Rfc3339;
+use time::macros::format_description;
+use time::OffsetDateTime;
+
+use crate::config::SettingsState;
+use crate::error::{CommandError, CommandResult};
+use crate::lifecycle::{self, SidecarEvent};
+use crate::logfile::{self, FileSink};
+use crate::RPC_TIMEOUT;
+
+/// 日志文件名（不含扩展名），位于日志目录（见 `paths::log_dir`）下
+const LOG_FILE_NAME: &str = "nullgravity";
+/// 单个日志文件达到 5 MB 后轮转
+const LOG_MAX_BYTES: u64 = 5 * 1024 * 1024;
+/// 轮转后保留的历史文件数
+const LOG_KEEP_FILES: usize = 3;
+
+/// read_log_tail 单次最多读取的字节数，落后太多时分几次追上
+const LOG_TAIL_MAX_BYTES: u64 = 256 * 1024;
+
+/// 运行时可调整的日志级别，按 LevelFilter 的序号存放
+static ACTIVE_LEVEL: AtomicUsize = AtomicUsize::new(LevelFilter::Info as usize);
+
+const LEVELS: [LevelFilter; 6] = [
+    LevelFilter::Off,
+    LevelFilter::Error,
+    LevelFilter::Warn,
+    LevelFilter::Info,
+    LevelFilter::Debug,
+    LevelFilter::Trace,
+];
+
+/// 转发 sidecar 输出时使用的日志 target，附加服务为 `sidecar:<name>`
+pub const SIDECAR_TARGET: &str = "core";
+
+/// 日志文件的行格式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    #[default]
+    Text,
+    /// 每行一个 JSON 对象 `{ts, level, target, source, message}`
+    Json,
+}
+
+/// 与 ACTIVE_LEVEL 一样在运行时切换，插件构建时还拿不到 Settings
+static JSON_FORMAT: AtomicBool = AtomicBool::new(false);
+
+pub(crate) fn apply_format(format: LogFormat) {
+    JSON_FORMAT.store(format == LogFormat::Json, Ordering::Relaxed);
+}
+
+pub fn active_level() -> LevelFilter {
+    LEVELS[ACTIVE_LEVEL.load(Ordering::Relaxed)]
+}
+
+pub(crate) fn apply_level(level: LevelFilter) {
+    ACTIVE_LEVEL.store(level as usize, Ordering::Relaxed);
+    log::set_max_level(level);
+}
+
+/// 所有构建都写日志文件，stdout 只在 debug 下输出
+///
+/// 文件由 `logfile` 自己管理以支持按需轮转，setup 里调用 `open_file` 之前的日志先缓存在内存。
+pub fn plugin<R: Runtime>() -> TauriPlugin<R> {
+    let file = fern::Dispatch::new().chain(fern::Output::writer(Box::new(FileSink), "\n"));
+    let mut targets = vec![Target::new(TargetKind::Dispatch(file))];
+    if cfg!(debug_assertions) {
+        targets.push(Target::new(TargetKind::Stdout));
+    }
+
+    // 插件本身放行全部级别，实际过滤交给 ACTIVE_LEVEL，便于运行时调整
+    tauri_plugin_log::Builder::new()
+        .level(LevelFilter::Trace)
+        .filter(|metadata| metadata.level() <= active_level())
+        .format(|out, message, record| {
+            if JSON_FORMAT.load(Ordering::Relaxed) {
+                out.finish(format_args!("{}", json_line(message, record)))
+            } else {
+                let now = OffsetDateTime::now_utc()
+                    .format(format_description!(
+                        "[[[year]-[month]-[day]][[[hour]:[minute]:[second]]"
+                    ))
+                    .unwrap_or_default();
+                out.finish(format_args!(
+                    "{}[{}][{}] {}",
+                    now,
+                    record.target(),
+                    record.level(),
+                    message
+                ))
+            }
+        })
+        .targets(targets)
+        .build()
+}
+
+/// 打开日志目录下的日志文件，之后缓存的日志落盘
+pub fn open_file(app: &AppHandle) {
+    let result = log_file(app)
+        .map(PathBuf::from)
+        .and_then(|path| Ok(logfile::open(path, LOG_MAX_BYTES, LOG_KEEP_FILES)?));
+    if let Err(e) = result {
+        eprintln!("Failed to open log file: {}", e);
+    }
+}
+
+/// 刷新缓冲并换一个新的日志文件，返回刚关闭的文件路径，方便附到问题报告里
+#[tauri::command]
+pub async fn rotate_log() -> CommandResult<String> {
+    crate::task::blocking(|| {
+        log::logger().flush();
+        let closed = logfile::rotate()?;
+        log::info!("Log rotated, previous file at {}", closed.display());
+        Ok(closed.to_string_lossy().into_owned())
+    })
+    .await
+}
+
+fn json_line(message: &std::fmt::Arguments, record: &Record) -> String {
+    let target = record.target();
+    let source = if target == SIDECAR_TARGET || target.starts_with("sidecar:") {
+        "sidecar"
+    } else {
+        "app"
+    };
+    serde_json::json!({
+        "ts": OffsetDateTime::now_utc().format(&Rfc3339).unwrap_or_default(),
+        "level": record.level().as_str(),
+        "target": target,
+        "source": source,
+        "message": message.to_string(),
+    })
+    .to_string()
+}
+
+/// 从 sidecar 的一行输出里识别级别：JSON 的 `level` 字段，或行首的 `[WARN]` / `INFO:` 之类标记
+pub(crate) fn parse_severity(line: &str) -> Option<Level> {
+    let line = line.trim_start();
+    if line.starts_with('{') {
+        let value: serde_json::Value = serde_json::from_str(line).ok()?;
+        let level = value.get("level").or_else(|| value.get("levelname"))?;
+        return level_from_name(level.as_str()?);
+    }
+    let token = line.strip_prefix('[').unwrap_or(line);
+    let end = token
+        .find(|c: char| !c.is_ascii_alphabetic())
+        .unwrap_or(token.len());
+    level_from_name(&token[..end])
+}
+
+fn level_from_name(name: &str) -> Option<Level> {
+    match name.to_ascii_lowercase().as_str() {
+        "critical" | "crit" | "fatal" | "error" | "err" => Some(Level::Error),
+        "warning" | "warn" => Some(Level::Warn),
+        "info" => Some(Level::Info),
+        "debug" => Some(Level::Debug),
+        "trace" => Some(Level::Trace),
+        _ => None,
+    }
+}
+
+/// 当前日志文件的完整路径
+#[tauri::command]
+pub fn log_file_path(app: AppHandle) -> CommandResult<String> {
+    log_file(&app)
+}
+
+pub(crate) fn log_file(app: &AppHandle) -> CommandResult<String> {
+    let dir = crate::paths::log_dir(app)?;
+    Ok(dir
+        .join(format!("{}.log", LOG_FILE_NAME))
+        .to_string_lossy()
+        .into_owned())
+}
+
+/// 日志目录下当前及轮转出来的所有日志文件
+pub(crate) fn log_files(app: &AppHandle) -> CommandResult<Vec<PathBuf>> {
+    let dir = crate::paths::log_dir(app)?;
+    let mut files: Vec<PathBuf> = std::fs::read_dir(&dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.file_name().is_some_and(|name| {
+                let name = name.to_string_lossy();
+                name.starts_with(LOG_FILE_NAME) && name.ends_with(".log")
+            })
+        })
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+/// 日志查看器的读取位置，由调用方保存并在下次调用时原样传回
+///
+/// 每个查看器各自持有一个，互不影响。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LogCursor {
+    /// 读的是哪个文件，轮转后换了文件时从头读
+    pub file_id: u64,
+    pub offset: u64,
+}
+
+#[derive(Serialize)]
+pub struct LogTail {
+    pub lines: Vec<String>,
+    pub cursor: LogCursor,
+}
+
+/// 从 `cursor` 开始读取新写入的完整行；首次调用传 null，文件被轮转（变小或换了文件）时从头读
+#[tauri::command]
+pub async fn read_log_tail(app: AppHandle, cursor: Option<LogCursor>) -> CommandResult<LogTail> {
+    crate::task::blocking(move || {
+        log::logger().flush();
+        let path = PathBuf::from(log_file(&app)?);
+        Ok(read_tail(&path, cursor)?)
+    })
+    .await
+}
+
+fn read_tail(path: &Path, cursor: Option<LogCursor>) -> std::io::Result<LogTail> {
+    let mut file = File::open(path)?;
+    let meta = file.metadata()?;
+    let id = file_id(&meta);
+    let offset = match cursor {
+        Some(cursor) if cursor.file_id == id && cursor.offset <= meta.len() => cursor.offset,
+        _ => 0,
+    };
+
+    file.seek(SeekFrom::Start(offset))?;
+    let mut buf = Vec::new();
+    file.take(LOG_TAIL_MAX_BYTES).read_to_end(&mut buf)?;
+    // 只返回完整的行；一整块都没有换行时说明行太长，只能原样返回
+    let end = match buf.iter().rposition(|&b| b == b'\n') {
+        Some(pos) => pos + 1,
+        None if buf.len() as u64 == LOG_TAIL_MAX_BYTES => buf.len(),
+        None => 0,
+    };
+    let lines = String::from_utf8_lossy(&buf[..end])
+        .lines()
+        .map(str::to_string)
+        .collect();
+    Ok(LogTail {
+        lines,
+        cursor: LogCursor {
+            file_id: id,
+            offset: offset + end as u64,
+        },
+    })
+}
+
+/// 区分同名的不同文件：Unix 用 inode，Windows 用创建时间
+fn file_id(meta: &std::fs::Metadata) -> u64 {
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::MetadataExt::ino(meta)
+    }
+    #[cfg(windows)]
+    {
+        std::os::windows::fs::MetadataExt::creation_time(meta)
+    }
+}
+
+pub(crate) fn parse_level(level: &str) -> CommandResult<LevelFilter> {
+    level.trim().parse().map_err(|_| {
+        CommandError::InvalidArgument(format!(
+            "Unknown log level '{}', expected one of off/error/warn/info/debug/trace",
+            level
+        ))
+    })
+}
+
+/// 在文件管理器中打开日志目录，首次运行时先创建
+#[tauri::command]
+pub async fn open_log_dir(app: AppHandle) -> CommandResult<()> {
+    crate::task::blocking(move || {
+        let dir = crate::paths::log_dir(&app)?;
+        crate::config::open_dir(&app, &dir)
+    })
+    .await
+}
+
+/// 调整日志级别后如何同步给 sidecar
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevelSync {
+    /// 通过 stdin RPC 通知，不打断后端
+    #[default]
+    Rpc,
+    /// 带上新的 RUST_LOG 重启 sidecar
+    Restart,
+    /// 只调整外壳自身
+    Off,
+}
+
+#[tauri::command]
+pub async fn set_log_level(app: AppHandle, level: String) -> CommandResult<()> {
+    let parsed = parse_level(&level)?;
+    // 需要重启才能同步时，先确认现在能重启，免得外壳的级别已经改了命令却报错
+    if app.state::<SettingsState>().get().log_level_sync == LogLevelSync::Restart {
+        let sidecar = app.state::<crate::sidecar::SidecarState>();
+        if sidecar.is_shutting_down() {
+            return Err(CommandError::ShuttingDown);
+        }
+        sidecar.ensure_owned()?;
+    }
+    apply_level(parsed);
+    log::info!("Log level set to {}", parsed);
+    sync_level(&app).await;
+    Ok(())
+}
+
+/// 按 `log_level_sync` 把当前级别同步给 sidecar 并推送 LogLevel 事件，reload_settings 也经过这里
+///
+/// 外壳的级别此时已经生效，同步失败只记录警告，事件照常推送。
+pub(crate) async fn sync_level(app: &AppHandle) {
+    let sync = app.state::<SettingsState>().get().log_level_sync;
+    match sync {
+        LogLevelSync::Rpc => {
+            let params = serde_json::json!({ "level": sidecar_level() });
+            if let Err(e) = crate::rpc::call(app, "set_log_level", params, RPC_TIMEOUT).await {
+                log::warn!("Failed to pass log level to the sidecar: {}", e);
+            }
+        }
+        LogLevelSync::Restart => {
+            let handle = app.clone();
+            let restarted =
+                crate::task::blocking(move || crate::sidecar::restart_unless_tripped(&handle))
+                    .await;
+            if let Err(e) = restarted {
+                log::warn!(
+                    "Failed to restart the sidecar with the new log level: {}",
+                    e
+                );
+            }
+        }
+        LogLevelSync::Off => {}
+    }
+    lifecycle::emit(
+        app,
+        SidecarEvent::LogLevel {
+            level: sidecar_level(),
+            sync,
+        },
+    );
+}
+
+/// 传给 sidecar 的级别（RUST_LOG 风格的小写名）
+pub(crate) fn sidecar_level() -> String {
+    active_level().as_str().to_ascii_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    fn append(path: &Path, text: &str) {
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .unwrap();
+        file.write_all(text.as_bytes()).unwrap();
+    }
+
+    #[test]
+    fn severity_comes_from_prefixes_and_json_fields() {
+        assert_eq!(parse_severity("[WARN] disk almost full"), Some(Level::Warn));
+        assert_eq!(parse_severity("INFO: Started server"), Some(Level::Info));
+        assert_eq!(parse_severity("  ERROR boom"), Some(Level::Error));
+        assert_eq!(parse_severity("[debug] tick"), Some(Level::Debug));
+        assert_eq!(
+            parse_severity("CRITICAL: out of memory"),
+            Some(Level::Error)
+        );
+        assert_eq!(
+            parse_severity(r#"{"level": "warning", "msg": "slow"}"#),
+            Some(Level::Warn)
+        );
+        assert_eq!(
+            parse_severity(r#"{"levelname": "TRACE"}"#),
+            Some(Level::Trace)
+        );
+    }
+
+    #[test]
+    fn unknown_lines_have_no_severity() {
+        for line in [
+            "Started server process",
+            "warnings: 3",
+            "12:00:01 INFO late prefix",
+            "{not json",
+            r#"{"level": 3}"#,
+            r#"{"msg": "no level"}"#,
+            "",
+        ] {
+            assert_eq!(parse_severity(line), None, "{:?}", line);
+        }
+    }
+
+    #[test]
+    fn cursors_survive_rotation_independently() {
+        let dir = std::env::temp_dir().join(format!("nullgravity-tail-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("app.log");
+        let _ = std::fs::remove_file(&path);
+        append(&path, "one\ntwo\n");
+
+        let first = read_tail(&path, None).unwrap();
+        assert_eq!(first.lines, ["one", "two"]);
+        let second = read_tail(&path, None).unwrap();
+        assert_eq!(second.cursor, first.cursor);
+
+        append(&path, "three\n");
+        let first = read_tail(&path, Some(first.cursor)).unwrap();
+        assert_eq!(first.lines, ["three"]);
+
+        // 轮转：旧文件改名，新文件比两个查看器的偏移都长
+        std::fs::rename(&path, dir.join("app.1.log")).unwrap();
+        append(&path, "fresh line that is long enough\nnext\n");
+        let rotated = read_tail(&path, Some(first.cursor)).unwrap();
+        assert_eq!(rotated.lines, ["fresh line that is long enough", "next"]);
+        // 另一个查看器还拿着旧文件的位置，先来的调用不能影响它
+        let stale = read_tail(&path, Some(second.cursor)).unwrap();
+        assert_eq!(stale.lines, rotated.lines);
+        assert_eq!(stale.cursor, rotated.cursor);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    fn tail_file(name: &str) -> (PathBuf, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("nullgravity-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("app.log");
+        let _ = std::fs::remove_file(&path);
+        (dir, path)
+    }
+
+    #[test]
+    fn partial_lines_wait_for_their_newline() {
+        let (dir, path) = tail_file("tail-partial");
+        append(&path, "done\nhalf");
+        let tail = read_tail(&path, None).unwrap();
+        assert_eq!(tail.lines, ["done"]);
+        assert_eq!(tail.cursor.offset, 5);
+
+        append(&path, " line\n");
+        let tail = read_tail(&path, Some(tail.cursor)).unwrap();
+        assert_eq!(tail.lines, ["half line"]);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn truncated_or_replaced_files_are_read_from_the_start() {
+        let (dir, path) = tail_file("tail-truncate");
+        append(&path, "a much longer first line\n");
+        let before = read_tail(&path, None).unwrap();
+
+        // 同一个文件被截短，偏移超出了文件长度
+        std::fs::write(&path, "short\n").unwrap();
+        let truncated = read_tail(&path, Some(before.cursor)).unwrap();
+        assert_eq!(truncated.lines, ["short"]);
+
+        // 偏移仍在文件范围内，但 file_id 对不上，说明已经不是同一个文件
+        append(&path, "more\n");
+        let foreign = LogCursor {
+            file_id: truncated.cursor.file_id.wrapping_add(1),
+            offset: truncated.cursor.offset,
+        };
+        let replaced = read_tail(&path, Some(foreign)).unwrap();
+        assert_eq!(replaced.lines, ["short", "more"]);
+        assert_eq!(replaced.cursor.file_id, truncated.cursor.file_id);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
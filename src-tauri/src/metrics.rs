@@ -0,0 +1,80 @@
+use std::collections::HashSet;
+use std::sync::Mutex;
+
+use serde::Serialize;
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System};
+use tauri::{AppHandle, Manager};
+
+use crate::error::CommandResult;
+use crate::sidecar::SidecarState;
+use crate::task::blocking;
+
+/// 复用同一个 System 实例，cpu_usage 依赖两次刷新之间的差值
+#[derive(Default)]
+pub struct MetricsState(Mutex<System>);
+
+#[derive(Serialize)]
+pub struct SidecarMetrics {
+    pub cpu_percent: f32,
+    pub memory_bytes: u64,
+    pub uptime_secs: u64,
+    /// 前端消费跟不上时丢弃的输出行数
+    pub dropped_lines: u64,
+}
+
+/// sidecar 及其子进程的资源占用（PyInstaller 单文件包的真正工作进程是子进程）
+#[tauri::command]
+pub async fn sidecar_metrics(app: AppHandle) -> CommandResult<Option<SidecarMetrics>> {
+    blocking(move || Ok(collect(&app))).await
+}
+
+fn collect(app: &AppHandle) -> Option<SidecarMetrics> {
+    let sidecar = app.state::<SidecarState>();
+    let metrics = app.state::<MetricsState>();
+    let (pid, dropped_lines) = sidecar
+        .lock_child()
+        .as_ref()
+        .map(|c| (c.pid(), c.dropped_lines()))?;
+    let uptime_secs = sidecar.uptime().map(|d| d.as_secs()).unwrap_or_default();
+
+    let mut sys = metrics.0.lock().unwrap();
+    sys.refresh_processes_specifics(
+        ProcessesToUpdate::All,
+        true,
+        ProcessRefreshKind::nothing().with_cpu().with_memory(),
+    );
+
+    let root = Pid::from_u32(pid);
+    sys.process(root)?;
+
+    // 收集整棵进程树
+    let mut tree = HashSet::from([root]);
+    loop {
+        let before = tree.len();
+        for (child, process) in sys.processes() {
+            if process
+                .parent()
+                .is_some_and(|parent| tree.contains(&parent))
+            {
+                tree.insert(*child);
+            }
+        }
+        if tree.len() == before {
+            break;
+        }
+    }
+
+    let (cpu_percent, memory_bytes) = tree
+        .iter()
+        .filter_map(|pid| sys.process(*pid))
+        .fold((0.0, 0), |(cpu, mem), p| {
+            (cpu + p.cpu_usage(), mem + p.memory())
+        });
+
+    Some(SidecarMetrics {
+        cpu_percent,
+        memory_bytes,
+        uptime_secs,
+        dropped_lines,
+    })
+}
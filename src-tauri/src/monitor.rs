@@ -0,0 +1,102 @@
+use serde::Serialize;
+use tauri::{AppHandle, Manager, Monitor, PhysicalPosition, WebviewWindow};
+
+use crate::error::{CommandError, CommandResult};
+
+/// 一个显示器的信息（物理像素），`index` 即 move_window_to_monitor 的参数
+#[derive(Serialize)]
+pub struct MonitorInfo {
+    pub index: usize,
+    pub name: Option<String>,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub scale_factor: f64,
+    pub primary: bool,
+}
+
+/// 两个 Monitor 是否是同一块屏：没有唯一 ID，按名字和位置比较
+fn same(a: &Monitor, b: &Monitor) -> bool {
+    a.name() == b.name() && a.position() == b.position()
+}
+
+#[tauri::command]
+pub fn list_monitors(app: AppHandle) -> CommandResult<Vec<MonitorInfo>> {
+    let primary = app.primary_monitor()?;
+    Ok(app
+        .available_monitors()?
+        .iter()
+        .enumerate()
+        .map(|(index, monitor)| MonitorInfo {
+            index,
+            name: monitor.name().cloned(),
+            x: monitor.position().x,
+            y: monitor.position().y,
+            width: monitor.size().width,
+            height: monitor.size().height,
+            scale_factor: monitor.scale_factor(),
+            primary: primary.as_ref().is_some_and(|p| same(p, monitor)),
+        })
+        .collect())
+}
+
+/// 把主窗口移到第 `index` 个显示器（list_monitors 的顺序）并居中
+#[tauri::command]
+pub fn move_window_to_monitor(app: AppHandle, index: usize) -> CommandResult<()> {
+    let window = app
+        .get_webview_window("main")
+        .ok_or(tauri::Error::WindowNotFound)?;
+    let monitors = window.available_monitors()?;
+    let monitor = monitors.get(index).ok_or_else(|| {
+        CommandError::InvalidArgument(format!(
+            "monitor {} does not exist, {} connected",
+            index,
+            monitors.len()
+        ))
+    })?;
+    center_on(&window, monitor)?;
+    log::info!(
+        "Moved main window to monitor {} ({})",
+        index,
+        monitor.name().map(String::as_str).unwrap_or("unnamed")
+    );
+    Ok(())
+}
+
+/// 在指定显示器上居中；最大化的窗口先还原，否则移不动
+pub fn center_on(window: &WebviewWindow, monitor: &Monitor) -> tauri::Result<()> {
+    if window.is_maximized()? {
+        window.unmaximize()?;
+    }
+    let size = window.outer_size()?;
+    let area = monitor.size();
+    let origin = monitor.position();
+    let x = origin.x + (area.width.saturating_sub(size.width) / 2) as i32;
+    let y = origin.y + (area.height.saturating_sub(size.height) / 2) as i32;
+    window.set_position(PhysicalPosition::new(x, y))
+}
+
+/// 保存的显示器已拔掉（比如笔记本离开扩展坞）时退回主显示器
+pub fn center_on_primary(window: &WebviewWindow) {
+    let result = match window.primary_monitor() {
+        Ok(Some(primary)) => center_on(window, &primary),
+        _ => window.center(),
+    };
+    if let Err(e) = result {
+        log::warn!("Failed to center window on the primary monitor: {}", e);
+    }
+}
+
+/// 名为 `name` 的显示器当前是否还连着
+pub fn is_connected(window: &WebviewWindow, name: &str) -> bool {
+    window
+        .available_monitors()
+        .map(|monitors| {
+            monitors
+                .iter()
+                .any(|m| m.name().map(String::as_str) == Some(name))
+        })
+        // 查询失败时不要因此挪窗口
+        .unwrap_or(true)
+}
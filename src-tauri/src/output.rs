@@ -0,0 +1,278 @@
+use std::collections::{BTreeSet, VecDeque};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+use tauri::ipc::Channel;
+use tauri::{AppHandle, Emitter, Manager, State, Window, WindowEvent};
+
+use crate::sidecar::lock_recover;
+
+/// 内存中保留的最近 sidecar 输出行数
+const RECENT_LOG_CAPACITY: usize = 1000;
+/// 输出攒批推送：最多等这么久，或攒够这么多行就立即推
+const LOG_BATCH_INTERVAL: Duration = Duration::from_millis(100);
+const LOG_BATCH_MAX_LINES: usize = 50;
+
+/// 推给前端的单行 sidecar 输出，连续重复的行合并并记录次数
+#[derive(Clone, Serialize)]
+pub struct SidecarLog {
+    pub stream: &'static str,
+    /// 转发时使用的日志级别，如 "INFO"、"WARN"
+    pub level: &'static str,
+    pub line: String,
+    pub ts: u64,
+    pub repeat: u32,
+    /// chunks 模式下的原始输出块：可能不以换行结尾、含 `\r`，前端应按终端语义直接拼接
+    pub chunk: bool,
+}
+
+/// chunks 模式下把原始输出块还原成行，供日志文件和控制协议使用
+///
+/// 保留被截断的 UTF-8 字符，等下一块补齐再解码；行内的 `\r` 按终端语义只留最后一段。
+#[derive(Default)]
+pub struct ChunkAssembler {
+    carry: Vec<u8>,
+    pending: String,
+}
+
+impl ChunkAssembler {
+    /// 追加一块输出，返回可立即转发的文本和其中已经完结的行
+    pub fn push(&mut self, bytes: &[u8]) -> (String, Vec<String>) {
+        self.carry.extend_from_slice(bytes);
+        let valid = match std::str::from_utf8(&self.carry) {
+            Ok(_) => self.carry.len(),
+            // 末尾是不完整的多字节字符，留到下一块
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            Err(_) => self.carry.len(),
+        };
+        let rest = self.carry.split_off(valid);
+        let text = String::from_utf8_lossy(&self.carry).into_owned();
+        self.carry = rest;
+
+        self.pending.push_str(&text);
+        let mut lines = Vec::new();
+        while let Some(end) = self.pending.find('\n') {
+            let line: String = self.pending.drain(..=end).collect();
+            let line = line.trim_end_matches(['\r', '\n']);
+            lines.push(line.rsplit('\r').next().unwrap_or_default().to_string());
+        }
+        (text, lines)
+    }
+
+    /// 还有没遇到换行的内容
+    pub fn has_pending(&self) -> bool {
+        !self.pending.is_empty() || !self.carry.is_empty()
+    }
+}
+
+/// 一批输出，`seq` 单调递增，前端据此发现丢批
+#[derive(Clone, Serialize)]
+struct SidecarLogBatch {
+    seq: u64,
+    lines: Vec<SidecarLog>,
+}
+
+/// 等待推送的输出
+#[derive(Default)]
+pub struct LogBatcher {
+    pending: Mutex<Vec<SidecarLog>>,
+    next_seq: AtomicU64,
+    flush_scheduled: AtomicBool,
+}
+
+impl LogBatcher {
+    /// 加入一行，返回是否已攒满一批
+    fn push(&self, log: SidecarLog) -> bool {
+        let mut pending = lock_recover(&self.pending, "Log batch");
+        if let Some(last) = pending.last_mut() {
+            // 原始块必须原样拼接，不合并
+            if !log.chunk && !last.chunk && last.stream == log.stream && last.line == log.line {
+                last.repeat += 1;
+                last.ts = log.ts;
+                return false;
+            }
+        }
+        pending.push(log);
+        pending.len() >= LOG_BATCH_MAX_LINES
+    }
+
+    /// 编号和发送都在锁内完成，保证批次按 seq 顺序到达；只发给订阅了的窗口
+    fn flush(&self, app: &AppHandle) {
+        let mut pending = lock_recover(&self.pending, "Log batch");
+        if pending.is_empty() {
+            return;
+        }
+        let batch = SidecarLogBatch {
+            seq: self.next_seq.fetch_add(1, Ordering::SeqCst),
+            lines: std::mem::take(&mut *pending),
+        };
+        for label in app.state::<LogWindows>().labels() {
+            let _ = app.emit_to(label.as_str(), "sidecar-log-batch", batch.clone());
+        }
+    }
+}
+
+/// 通过 subscribe_log_batches 订阅了 `sidecar-log-batch` 事件的窗口
+///
+/// 没有窗口订阅时输出根本不进入攒批，启动画面这类窗口也就不会收到高频的日志推送。
+#[derive(Default)]
+pub struct LogWindows(Mutex<BTreeSet<String>>);
+
+impl LogWindows {
+    fn labels(&self) -> Vec<String> {
+        lock_recover(&self.0, "Log windows")
+            .iter()
+            .cloned()
+            .collect()
+    }
+
+    fn is_empty(&self) -> bool {
+        lock_recover(&self.0, "Log windows").is_empty()
+    }
+}
+
+/// 调用方窗口开始接收 `sidecar-log-batch`
+#[tauri::command]
+pub fn subscribe_log_batches(window: Window, state: State<LogWindows>) {
+    if lock_recover(&state.0, "Log windows").insert(window.label().to_string()) {
+        log::debug!("Window '{}' subscribed to sidecar output", window.label());
+    }
+}
+
+#[tauri::command]
+pub fn unsubscribe_log_batches(window: Window, state: State<LogWindows>) {
+    lock_recover(&state.0, "Log windows").remove(window.label());
+}
+
+/// 窗口销毁时取消订阅，页面没来得及调用 unsubscribe_log_batches 也不会留下
+pub fn track(window: &Window, event: &WindowEvent) {
+    if matches!(event, WindowEvent::Destroyed) {
+        let windows = window.app_handle().state::<LogWindows>();
+        lock_recover(&windows.0, "Log windows").remove(window.label());
+    }
+}
+
+/// 最近的 sidecar 输出，供用户事后打开控制台时查看
+#[derive(Default)]
+pub struct LogBuffer(Mutex<RecentLines>);
+
+#[derive(Default)]
+struct RecentLines {
+    lines: VecDeque<String>,
+    /// 累计写入过的行数，包括已经被挤出去的
+    total: u64,
+}
+
+impl LogBuffer {
+    fn push(&self, line: String) {
+        let mut recent = lock_recover(&self.0, "Log buffer");
+        if recent.lines.len() >= RECENT_LOG_CAPACITY {
+            recent.lines.pop_front();
+        }
+        recent.lines.push_back(line);
+        recent.total += 1;
+    }
+
+    /// 累计计数 `seen` 之后新写入、仍在缓冲区里的行，以及当前的累计计数
+    pub fn since(&self, seen: u64) -> (Vec<String>, u64) {
+        let recent = lock_recover(&self.0, "Log buffer");
+        let new = recent
+            .total
+            .saturating_sub(seen)
+            .min(recent.lines.len() as u64) as usize;
+        let skip = recent.lines.len() - new;
+        (
+            recent.lines.iter().skip(skip).cloned().collect(),
+            recent.total,
+        )
+    }
+}
+
+/// 通过 subscribe_logs 单独订阅输出的前端视图，只有它们会收到逐行推送
+#[derive(Default)]
+pub struct LogSubscribers(Mutex<Vec<Channel<SidecarLog>>>);
+
+impl LogSubscribers {
+    /// 推送给所有订阅者，发送失败（页面已关闭或刷新）的顺便移除
+    fn send(&self, log: &SidecarLog) {
+        let mut channels = lock_recover(&self.0, "Log subscribers");
+        if channels.is_empty() {
+            return;
+        }
+        channels.retain(|channel| channel.send(log.clone()).is_ok());
+    }
+}
+
+/// 订阅 sidecar 输出，返回的 id 可交给 unsubscribe_logs 主动取消
+#[tauri::command]
+pub fn subscribe_logs(state: State<LogSubscribers>, channel: Channel<SidecarLog>) -> u32 {
+    let id = channel.id();
+    lock_recover(&state.0, "Log subscribers").push(channel);
+    id
+}
+
+#[tauri::command]
+pub fn unsubscribe_logs(state: State<LogSubscribers>, id: u32) {
+    lock_recover(&state.0, "Log subscribers").retain(|channel| channel.id() != id);
+}
+
+#[tauri::command]
+pub fn recent_logs(state: State<LogBuffer>) -> Vec<String> {
+    lock_recover(&state.0, "Log buffer")
+        .lines
+        .iter()
+        .cloned()
+        .collect()
+}
+
+/// 一行 sidecar 输出：写入缓冲区，攒批后推给前端
+pub fn publish(app: &AppHandle, stream: &'static str, level: log::Level, line: String) {
+    publish_log(app, stream, level, line, false);
+}
+
+/// chunks 模式下的一块原始输出；缓冲区里同样按块保存，拼接后即原始输出
+pub fn publish_chunk(app: &AppHandle, stream: &'static str, level: log::Level, text: String) {
+    publish_log(app, stream, level, text, true);
+}
+
+fn publish_log(
+    app: &AppHandle,
+    stream: &'static str,
+    level: log::Level,
+    line: String,
+    chunk: bool,
+) {
+    app.state::<LogBuffer>().push(line.clone());
+
+    let ts = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default();
+    let log = SidecarLog {
+        stream,
+        level: level.as_str(),
+        line,
+        ts,
+        repeat: 1,
+        chunk,
+    };
+    app.state::<LogSubscribers>().send(&log);
+    if app.state::<LogWindows>().is_empty() {
+        return;
+    }
+    let batcher = app.state::<LogBatcher>();
+    let full = batcher.push(log);
+    if full {
+        batcher.flush(app);
+    } else if !batcher.flush_scheduled.swap(true, Ordering::SeqCst) {
+        let handle = app.clone();
+        tauri::async_runtime::spawn(async move {
+            tokio::time::sleep(LOG_BATCH_INTERVAL).await;
+            let batcher = handle.state::<LogBatcher>();
+            batcher.flush_scheduled.store(false, Ordering::SeqCst);
+            batcher.flush(&handle);
+        });
+    }
+}
@@ -0,0 +1,67 @@
+use std::path::{Path, PathBuf};
+
+use tauri::{AppHandle, Manager};
+
+use crate::error::CommandResult;
+
+/// `--data-dir` 下的子目录划分
+const CONFIG_SUBDIR: &str = "config";
+const LOG_SUBDIR: &str = "logs";
+const DATA_SUBDIR: &str = "data";
+
+/// 传给 sidecar 的数据目录环境变量，后端的数据库据此定位
+pub(crate) const SIDECAR_DATA_DIR_ENV: &str = "NULLGRAVITY_DATA_DIR";
+
+/// 命令行指定的数据根目录；未指定时各目录使用平台默认位置
+pub struct AppDirs {
+    root: Option<PathBuf>,
+}
+
+impl AppDirs {
+    /// 指定了根目录时先把整棵目录树建好
+    pub fn new(root: Option<PathBuf>) -> Self {
+        if let Some(root) = &root {
+            for sub in [CONFIG_SUBDIR, LOG_SUBDIR, DATA_SUBDIR] {
+                if let Err(e) = std::fs::create_dir_all(root.join(sub)) {
+                    eprintln!("Failed to create {}: {}", root.join(sub).display(), e);
+                }
+            }
+        }
+        Self { root }
+    }
+
+    pub fn root(&self) -> Option<&Path> {
+        self.root.as_deref()
+    }
+}
+
+/// 配置目录：settings.toml、窗口位置等
+pub fn config_dir(app: &AppHandle) -> CommandResult<PathBuf> {
+    match app.state::<AppDirs>().root() {
+        Some(root) => Ok(root.join(CONFIG_SUBDIR)),
+        None => Ok(app.path().app_config_dir()?),
+    }
+}
+
+/// 日志目录
+pub fn log_dir(app: &AppHandle) -> CommandResult<PathBuf> {
+    match app.state::<AppDirs>().root() {
+        Some(root) => Ok(root.join(LOG_SUBDIR)),
+        None => Ok(app.path().app_log_dir()?),
+    }
+}
+
+/// 数据目录：诊断包等
+pub fn data_dir(app: &AppHandle) -> CommandResult<PathBuf> {
+    match app.state::<AppDirs>().root() {
+        Some(root) => Ok(root.join(DATA_SUBDIR)),
+        None => Ok(app.path().app_data_dir()?),
+    }
+}
+
+/// 只有指定了 `--data-dir` 时才覆盖 sidecar 的数据目录，否则沿用后端自己的默认位置
+pub fn sidecar_data_dir(app: &AppHandle) -> Option<PathBuf> {
+    app.state::<AppDirs>()
+        .root()
+        .map(|root| root.join(DATA_SUBDIR))
+}
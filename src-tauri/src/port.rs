@@ -0,0 +1,74 @@
+//! 后端端口的挑选，不依赖 Tauri 运行时
+use std::io;
+use std::net::TcpListener;
+
+/// 让系统分配一个空闲端口后立即释放
+pub fn pick_free_port() -> io::Result<u16> {
+    Ok(TcpListener::bind("127.0.0.1:0")?.local_addr()?.port())
+}
+
+/// 在 `lo..=hi` 内按顺序找第一个能绑定的端口，供只能放行固定端口段的防火墙环境使用
+pub fn pick_free_port_in_range(lo: u16, hi: u16) -> io::Result<u16> {
+    if lo == 0 || lo > hi {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("invalid port range {}-{}", lo, hi),
+        ));
+    }
+    (lo..=hi).find(|&port| is_free(port)).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::AddrInUse,
+            format!("no free port in {}-{}", lo, hi),
+        )
+    })
+}
+
+pub fn is_free(port: u16) -> bool {
+    TcpListener::bind(("127.0.0.1", port)).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_distinct_valid_ports() {
+        // 占住已选出的端口，系统就不会再分配同一个
+        let mut held = Vec::new();
+        let mut ports = Vec::new();
+        for _ in 0..10 {
+            let port = pick_free_port().unwrap();
+            assert_ne!(port, 0);
+            assert!(!ports.contains(&port), "port {} picked twice", port);
+            held.push(TcpListener::bind(("127.0.0.1", port)).unwrap());
+            ports.push(port);
+        }
+    }
+
+    #[test]
+    fn range_skips_a_bound_port() {
+        let taken = TcpListener::bind("127.0.0.1:0").unwrap();
+        let lo = taken.local_addr().unwrap().port();
+        let hi = lo.saturating_add(50);
+
+        let port = pick_free_port_in_range(lo, hi).unwrap();
+        assert_ne!(port, lo);
+        assert!((lo..=hi).contains(&port));
+        assert!(is_free(port));
+    }
+
+    #[test]
+    fn fully_bound_range_is_an_error() {
+        let taken = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = taken.local_addr().unwrap().port();
+
+        let err = pick_free_port_in_range(port, port).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AddrInUse);
+    }
+
+    #[test]
+    fn inverted_range_is_rejected() {
+        let err = pick_free_port_in_range(9000, 8000).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+}
@@ -0,0 +1,770 @@
+//! sidecar 子进程：自行 spawn，以便放进独立的进程组 / Job Object，关闭时连同后代一起结束
+use std::collections::VecDeque;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::process::{ChildStdin, Command, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::Notify;
+
+use crate::sidecar::lock_recover;
+
+/// 输出队列容量（行），消费跟不上时丢弃最旧的行，换取长时间运行下内存有界
+const EVENT_BUFFER: usize = 10_000;
+/// 进程退出后等待读线程把剩余输出读完的上限，防止被继承了管道的孙进程拖住
+const OUTPUT_DRAIN: Duration = Duration::from_millis(200);
+
+#[cfg(windows)]
+pub(crate) const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+/// 子进程输出与退出事件，stdout/stderr 按行切分
+pub enum ProcessEvent {
+    Stdout(Vec<u8>),
+    Stderr(Vec<u8>),
+    Terminated(ExitStatus),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ExitStatus {
+    pub code: Option<i32>,
+    pub signal: Option<i32>,
+}
+
+impl From<std::process::ExitStatus> for ExitStatus {
+    fn from(status: std::process::ExitStatus) -> Self {
+        #[cfg(unix)]
+        let signal = std::os::unix::process::ExitStatusExt::signal(&status);
+        #[cfg(not(unix))]
+        let signal = None;
+        Self {
+            code: status.code(),
+            signal,
+        }
+    }
+}
+
+/// 关闭 sidecar 的结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShutdownOutcome {
+    Graceful,
+    ForcedAfterTimeout,
+    AlreadyDead,
+    /// 强杀后仍然存活
+    Stuck,
+}
+
+/// 两段式关闭的等待时长：请求退出后等 `grace`，强杀后再等 `hard`
+#[derive(Debug, Clone, Copy)]
+pub struct StopTimeouts {
+    pub grace: Duration,
+    pub hard: Duration,
+}
+
+/// 输出事件队列的共享部分
+#[derive(Default)]
+struct Queue {
+    events: Mutex<VecDeque<ProcessEvent>>,
+    notify: Notify,
+    /// 已发出 Terminated，之后不再有事件
+    finished: AtomicBool,
+    /// 接收端已丢弃，读线程随之退出
+    abandoned: AtomicBool,
+    dropped: AtomicU64,
+}
+
+impl Queue {
+    /// 入队一行输出，满了就挤掉最旧的一行；接收端已不在时返回 false
+    fn push_line(&self, pid: u32, event: ProcessEvent) -> bool {
+        if self.abandoned.load(Ordering::SeqCst) {
+            return false;
+        }
+        let mut events = lock_recover(&self.events, "Sidecar output queue");
+        if events.len() >= EVENT_BUFFER {
+            events.pop_front();
+            if self.dropped.fetch_add(1, Ordering::Relaxed) == 0 {
+                log::warn!(
+                    "Sidecar PID={} output is not being consumed fast enough, dropping oldest lines",
+                    pid
+                );
+            }
+        }
+        events.push_back(event);
+        drop(events);
+        self.notify.notify_one();
+        true
+    }
+
+    /// 退出事件不受容量限制，保证监管方一定能收到
+    fn finish(&self, status: ExitStatus) {
+        lock_recover(&self.events, "Sidecar output queue")
+            .push_back(ProcessEvent::Terminated(status));
+        self.finished.store(true, Ordering::SeqCst);
+        self.notify.notify_one();
+    }
+}
+
+/// 子进程的输出事件，按到达顺序读取
+pub struct Events(Arc<Queue>);
+
+impl Events {
+    /// 等待下一个事件，Terminated 之后返回 None
+    pub async fn recv(&mut self) -> Option<ProcessEvent> {
+        loop {
+            let next = lock_recover(&self.0.events, "Sidecar output queue").pop_front();
+            if let Some(event) = next {
+                return Some(event);
+            }
+            if self.0.finished.load(Ordering::SeqCst) {
+                return None;
+            }
+            // notify_one 在没有等待者时会留下许可，先检查再等待不会丢事件
+            self.0.notify.notified().await;
+        }
+    }
+}
+
+impl Drop for Events {
+    fn drop(&mut self) {
+        self.0.abandoned.store(true, Ordering::SeqCst);
+    }
+}
+
+/// 如何读取 sidecar 的 stdout/stderr
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputMode {
+    /// 按换行切分，每个事件是一整行
+    #[default]
+    Lines,
+    /// 读到多少就交出多少，不等换行；适合用 `\r` 刷新的进度条等输出
+    Chunks,
+}
+
+/// chunks 模式下单次读取的上限
+const CHUNK_SIZE: usize = 8 * 1024;
+
+/// sidecar 的调度优先级，避免后端满载时拖慢界面
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    Low,
+    #[default]
+    Normal,
+    /// 通常需要更高权限，失败时保持原优先级
+    High,
+}
+
+/// 正在运行的 sidecar 句柄；Unix 下 PID 同时也是进程组 ID
+pub struct SidecarChild {
+    pid: u32,
+    /// 管道断开后置为 None，之后的写入直接报 BrokenPipe
+    stdin: Option<ChildStdin>,
+    queue: Arc<Queue>,
+    /// 分配失败时为 None，退回只处理直接子进程
+    #[cfg(windows)]
+    job: Option<job::JobObject>,
+}
+
+/// 启动子进程并按行读取输出，返回事件通道与进程句柄
+pub fn spawn(command: Command) -> io::Result<(Events, SidecarChild)> {
+    spawn_with(command, OutputMode::Lines)
+}
+
+/// 同 `spawn`，按 `mode` 读取输出
+pub fn spawn_with(mut command: Command, mode: OutputMode) -> io::Result<(Events, SidecarChild)> {
+    command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        command.process_group(0);
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        command.creation_flags(CREATE_NO_WINDOW);
+    }
+
+    let mut child = command.spawn()?;
+    let pid = child.id();
+    #[cfg(windows)]
+    let job = match job::JobObject::assign(&child) {
+        Ok(job) => Some(job),
+        Err(e) => {
+            log::warn!("Failed to put sidecar PID={} into a job object: {}", pid, e);
+            None
+        }
+    };
+
+    let (Some(stdin), Some(stdout), Some(stderr)) =
+        (child.stdin.take(), child.stdout.take(), child.stderr.take())
+    else {
+        let _ = child.kill();
+        return Err(io::Error::other("sidecar stdio was not piped"));
+    };
+
+    let queue = Arc::new(Queue::default());
+    type Pipe = Box<dyn Read + Send>;
+    let read: fn(u32, Pipe, Arc<Queue>, fn(Vec<u8>) -> ProcessEvent) -> JoinHandle<()> = match mode
+    {
+        OutputMode::Lines => read_lines::<Pipe>,
+        OutputMode::Chunks => read_chunks::<Pipe>,
+    };
+    let readers = [
+        read(pid, Box::new(stdout), queue.clone(), ProcessEvent::Stdout),
+        read(pid, Box::new(stderr), queue.clone(), ProcessEvent::Stderr),
+    ];
+    let waiter = queue.clone();
+    std::thread::spawn(move || {
+        let status = match child.wait() {
+            Ok(status) => ExitStatus::from(status),
+            Err(e) => {
+                log::warn!("Failed to wait for sidecar PID={}: {}", pid, e);
+                ExitStatus {
+                    code: None,
+                    signal: None,
+                }
+            }
+        };
+        let deadline = Instant::now() + OUTPUT_DRAIN;
+        while !readers.iter().all(JoinHandle::is_finished) && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        waiter.finish(status);
+    });
+
+    Ok((
+        Events(queue.clone()),
+        SidecarChild {
+            pid,
+            stdin: Some(stdin),
+            queue,
+            #[cfg(windows)]
+            job,
+        },
+    ))
+}
+
+fn read_lines<R: Read + Send + 'static>(
+    pid: u32,
+    pipe: R,
+    queue: Arc<Queue>,
+    wrap: fn(Vec<u8>) -> ProcessEvent,
+) -> JoinHandle<()> {
+    std::thread::spawn(move || {
+        let mut reader = BufReader::new(pipe);
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            match reader.read_until(b'\n', &mut line) {
+                Ok(0) | Err(_) => break,
+                Ok(_) => {
+                    if !queue.push_line(pid, wrap(line.clone())) {
+                        break;
+                    }
+                }
+            }
+        }
+    })
+}
+
+fn read_chunks<R: Read + Send + 'static>(
+    pid: u32,
+    mut pipe: R,
+    queue: Arc<Queue>,
+    wrap: fn(Vec<u8>) -> ProcessEvent,
+) -> JoinHandle<()> {
+    std::thread::spawn(move || {
+        let mut buf = vec![0u8; CHUNK_SIZE];
+        loop {
+            match pipe.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => {
+                    if !queue.push_line(pid, wrap(buf[..n].to_vec())) {
+                        break;
+                    }
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(_) => break,
+            }
+        }
+    })
+}
+
+impl SidecarChild {
+    pub fn pid(&self) -> u32 {
+        self.pid
+    }
+
+    /// 因消费跟不上而丢弃的输出行数
+    pub fn dropped_lines(&self) -> u64 {
+        self.queue.dropped.load(Ordering::Relaxed)
+    }
+
+    pub fn write(&mut self, buf: &[u8]) -> io::Result<()> {
+        let Some(stdin) = self.stdin.as_mut() else {
+            return Err(io::Error::new(
+                io::ErrorKind::BrokenPipe,
+                "stdin pipe already closed",
+            ));
+        };
+        let result = stdin.write_all(buf).and_then(|_| stdin.flush());
+        if let Err(e) = &result {
+            if e.kind() == io::ErrorKind::BrokenPipe {
+                self.stdin = None;
+            }
+        }
+        result
+    }
+
+    /// 之前的写入发现 stdin 管道已断开
+    pub fn stdin_closed(&self) -> bool {
+        self.stdin.is_none()
+    }
+
+    /// 进程本身或它的任一后代还活着
+    pub fn is_alive(&self) -> bool {
+        #[cfg(unix)]
+        {
+            let group = -(self.pid as libc::pid_t);
+            unsafe { libc::kill(group, 0) == 0 }
+            || process_alive(self.pid)
+        }
+        #[cfg(windows)]
+        {
+            match &self.job {
+                Some(job) => job.active_processes() > 0,
+                None => process_alive(self.pid),
+            }
+        }
+    }
+
+    /// 两段式关闭，强杀后仍然存活时返回 Stuck 而不是无限阻塞
+    pub fn stop(&self, timeouts: StopTimeouts) -> ShutdownOutcome {
+        if !self.is_alive() {
+            return ShutdownOutcome::AlreadyDead;
+        }
+
+        log::info!(
+            "Stopping sidecar PID={} (grace {:?})",
+            self.pid,
+            timeouts.grace
+        );
+        self.terminate();
+        if self.wait_for_exit(timeouts.grace) {
+            return ShutdownOutcome::Graceful;
+        }
+
+        log::warn!(
+            "Sidecar PID={} ignored terminate request, killing",
+            self.pid
+        );
+        self.kill();
+        if self.wait_for_exit(timeouts.hard) {
+            return ShutdownOutcome::ForcedAfterTimeout;
+        }
+
+        log::error!(
+            "Sidecar PID={} is still alive {:?} after kill",
+            self.pid,
+            timeouts.hard
+        );
+        ShutdownOutcome::Stuck
+    }
+
+    /// 等整个进程组 / Job 都退出，而不只是直接子进程
+    fn wait_for_exit(&self, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        while Instant::now() < deadline {
+            if !self.is_alive() {
+                return true;
+            }
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        !self.is_alive()
+    }
+
+    /// 礼貌地请求退出：Unix 向整个进程组发 SIGTERM，Windows 不带 /F 的 taskkill /T 投递关闭消息
+    pub fn terminate(&self) {
+        #[cfg(unix)]
+        signal_group(self.pid, libc::SIGTERM);
+        #[cfg(windows)]
+        taskkill(self.pid, false);
+    }
+
+    /// 强制结束整个进程树
+    pub fn kill(&self) {
+        #[cfg(unix)]
+        signal_group(self.pid, libc::SIGKILL);
+        #[cfg(windows)]
+        {
+            let killed = self.job.as_ref().is_some_and(|job| job.terminate());
+            if !killed {
+                taskkill(self.pid, true);
+            }
+        }
+    }
+}
+
+/// 先按进程组发信号，组不存在（比如外部改了进程组）时退回只发给进程本身
+#[cfg(unix)]
+fn signal_group(pid: u32, signal: libc::c_int) {
+    let pid = pid as libc::pid_t;
+    unsafe {
+        if libc::kill(-pid, signal) != 0 {
+            libc::kill(pid, signal);
+        }
+    }
+}
+
+#[cfg(windows)]
+fn taskkill(pid: u32, force: bool) {
+    use std::os::windows::process::CommandExt;
+    let pid = pid.to_string();
+    let mut args = vec!["/T", "/PID", pid.as_str()];
+    if force {
+        args.insert(0, "/F");
+    }
+    let _ = Command::new("taskkill")
+        .args(args)
+        .creation_flags(CREATE_NO_WINDOW)
+        .status();
+}
+
+/// 允许从前端发给 sidecar 的信号：SIGHUP 通常用于重新加载配置，SIGUSR1/2 由后端自定义
+pub const ALLOWED_SIGNALS: &[&str] = &["SIGHUP", "SIGUSR1", "SIGUSR2"];
+
+/// 向进程（不含其子进程）发送 `ALLOWED_SIGNALS` 中的信号；Windows 没有对应机制，返回 Unsupported
+pub fn send_signal(pid: u32, name: &str) -> io::Result<()> {
+    #[cfg(unix)]
+    {
+        let sig = match name {
+            "SIGHUP" => libc::SIGHUP,
+            "SIGUSR1" => libc::SIGUSR1,
+            "SIGUSR2" => libc::SIGUSR2,
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("signal {} is not allowed", name),
+                ))
+            }
+        };
+        if unsafe { libc::kill(pid as libc::pid_t, sig) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+    #[cfg(windows)]
+    {
+        let _ = pid;
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("{} has no equivalent on Windows", name),
+        ))
+    }
+}
+
+/// 调整进程优先级：Unix 设置 nice 值，Windows 设置优先级类
+pub fn set_priority(pid: u32, priority: Priority) -> io::Result<()> {
+    #[cfg(unix)]
+    {
+        let nice = match priority {
+            Priority::Low => 10,
+            Priority::Normal => 0,
+            Priority::High => -5,
+        };
+        if unsafe { libc::setpriority(libc::PRIO_PROCESS as _, pid as libc::id_t, nice) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+    #[cfg(windows)]
+    {
+        use windows_sys::Win32::Foundation::CloseHandle;
+        use windows_sys::Win32::System::Threading::{
+            OpenProcess, SetPriorityClass, ABOVE_NORMAL_PRIORITY_CLASS,
+            BELOW_NORMAL_PRIORITY_CLASS, NORMAL_PRIORITY_CLASS, PROCESS_SET_INFORMATION,
+        };
+        let class = match priority {
+            Priority::Low => BELOW_NORMAL_PRIORITY_CLASS,
+            Priority::Normal => NORMAL_PRIORITY_CLASS,
+            Priority::High => ABOVE_NORMAL_PRIORITY_CLASS,
+        };
+        unsafe {
+            let handle = OpenProcess(PROCESS_SET_INFORMATION, 0, pid);
+            if handle.is_null() {
+                return Err(io::Error::last_os_error());
+            }
+            let ok = SetPriorityClass(handle, class) != 0;
+            let err = io::Error::last_os_error();
+            CloseHandle(handle);
+            if ok {
+                Ok(())
+            } else {
+                Err(err)
+            }
+        }
+    }
+}
+
+/// 按 PID 判断进程是否还活着
+pub fn process_alive(pid: u32) -> bool {
+    #[cfg(unix)]
+    {
+        unsafe { libc::kill(pid as libc::pid_t, 0) == 0 }
+    }
+    #[cfg(windows)]
+    {
+        use windows_sys::Win32::Foundation::{CloseHandle, STILL_ACTIVE};
+        use windows_sys::Win32::System::Threading::{
+            GetExitCodeProcess, OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION,
+        };
+        unsafe {
+            let handle = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
+            if handle.is_null() {
+                return false;
+            }
+            let mut code = 0u32;
+            let ok = GetExitCodeProcess(handle, &mut code) != 0;
+            CloseHandle(handle);
+            ok && code == STILL_ACTIVE as u32
+        }
+    }
+}
+
+#[cfg(windows)]
+mod job {
+    use std::io;
+    use std::os::windows::io::AsRawHandle;
+
+    use windows_sys::Win32::Foundation::{CloseHandle, HANDLE};
+    use windows_sys::Win32::System::JobObjects::{
+        AssignProcessToJobObject, CreateJobObjectW, JobObjectBasicAccountingInformation,
+        JobObjectExtendedLimitInformation, QueryInformationJobObject, SetInformationJobObject,
+        TerminateJobObject, JOBOBJECT_BASIC_ACCOUNTING_INFORMATION,
+        JOBOBJECT_EXTENDED_LIMIT_INFORMATION, JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE,
+    };
+
+    /// 带 KILL_ON_JOB_CLOSE 的 Job：句柄关闭（包括本进程意外退出）时其中所有进程一起结束
+    pub struct JobObject(HANDLE);
+
+    // 句柄只在持有 sidecar 锁时使用
+    unsafe impl Send for JobObject {}
+    unsafe impl Sync for JobObject {}
+
+    impl JobObject {
+        pub fn assign(child: &std::process::Child) -> io::Result<Self> {
+            unsafe {
+                let handle = CreateJobObjectW(std::ptr::null(), std::ptr::null());
+                if handle.is_null() {
+                    return Err(io::Error::last_os_error());
+                }
+                let job = Self(handle);
+
+                let mut info: JOBOBJECT_EXTENDED_LIMIT_INFORMATION = std::mem::zeroed();
+                info.BasicLimitInformation.LimitFlags = JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE;
+                let ok = SetInformationJobObject(
+                    job.0,
+                    JobObjectExtendedLimitInformation,
+                    &info as *const _ as *const _,
+                    std::mem::size_of::<JOBOBJECT_EXTENDED_LIMIT_INFORMATION>() as u32,
+                );
+                if ok == 0 {
+                    return Err(io::Error::last_os_error());
+                }
+                if AssignProcessToJobObject(job.0, child.as_raw_handle() as HANDLE) == 0 {
+                    return Err(io::Error::last_os_error());
+                }
+                Ok(job)
+            }
+        }
+
+        pub fn active_processes(&self) -> u32 {
+            unsafe {
+                let mut info: JOBOBJECT_BASIC_ACCOUNTING_INFORMATION = std::mem::zeroed();
+                let ok = QueryInformationJobObject(
+                    self.0,
+                    JobObjectBasicAccountingInformation,
+                    &mut info as *mut _ as *mut _,
+                    std::mem::size_of::<JOBOBJECT_BASIC_ACCOUNTING_INFORMATION>() as u32,
+                    std::ptr::null_mut(),
+                );
+                if ok == 0 {
+                    0
+                } else {
+                    info.ActiveProcesses
+                }
+            }
+        }
+
+        pub fn terminate(&self) -> bool {
+            unsafe { TerminateJobObject(self.0, 1) != 0 }
+        }
+    }
+
+    impl Drop for JobObject {
+        fn drop(&mut self) {
+            unsafe {
+                CloseHandle(self.0);
+            }
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::path::PathBuf;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use super::*;
+
+    const READY: &str = "NULLGRAVITY_READY";
+    const TIMEOUTS: StopTimeouts = StopTimeouts {
+        grace: Duration::from_secs(2),
+        hard: Duration::from_secs(2),
+    };
+
+    /// 回显 stdin 的假 sidecar，启动后打印就绪标记
+    const ECHO_SIDECAR: &str = "echo NULLGRAVITY_READY\nwhile read line; do echo \"$line\"; done\n";
+    /// 忽略 SIGTERM 的假 sidecar，只能被强杀
+    const STUBBORN_SIDECAR: &str =
+        "trap '' TERM\necho NULLGRAVITY_READY\nwhile read line; do :; done\n";
+
+    fn write_script(body: &str) -> PathBuf {
+        static NEXT: AtomicU32 = AtomicU32::new(0);
+        let path = std::env::temp_dir().join(format!(
+            "nullgravity-fake-sidecar-{}-{}.sh",
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::write(&path, body).unwrap();
+        path
+    }
+
+    fn start(script: &PathBuf) -> (Events, SidecarChild) {
+        let mut command = Command::new("sh");
+        command.arg(script);
+        spawn(command).unwrap()
+    }
+
+    fn runtime() -> tokio::runtime::Runtime {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap()
+    }
+
+    /// 读到包含 `needle` 的 stdout 行为止
+    fn wait_for_line(rx: &mut Events, needle: &str) {
+        runtime().block_on(async {
+            let found = tokio::time::timeout(Duration::from_secs(5), async {
+                while let Some(event) = rx.recv().await {
+                    if let ProcessEvent::Stdout(bytes) = event {
+                        if String::from_utf8_lossy(&bytes).contains(needle) {
+                            return true;
+                        }
+                    }
+                }
+                false
+            })
+            .await;
+            assert_eq!(found, Ok(true), "never saw {:?}", needle);
+        });
+    }
+
+    fn wait_for_exit_event(rx: &mut Events) -> ExitStatus {
+        runtime().block_on(async {
+            tokio::time::timeout(Duration::from_secs(5), async {
+                while let Some(event) = rx.recv().await {
+                    if let ProcessEvent::Terminated(status) = event {
+                        return status;
+                    }
+                }
+                panic!("event channel closed without Terminated");
+            })
+            .await
+            .expect("sidecar did not report termination")
+        })
+    }
+
+    #[test]
+    fn reads_ready_line_and_echoes_stdin() {
+        let script = write_script(ECHO_SIDECAR);
+        let (mut rx, mut child) = start(&script);
+        wait_for_line(&mut rx, READY);
+
+        child.write(b"hello sidecar\n").unwrap();
+        wait_for_line(&mut rx, "hello sidecar");
+
+        assert_eq!(child.stop(TIMEOUTS), ShutdownOutcome::Graceful);
+        let _ = std::fs::remove_file(script);
+    }
+
+    #[test]
+    fn restart_replaces_the_process() {
+        let script = write_script(ECHO_SIDECAR);
+        let (mut rx, old) = start(&script);
+        wait_for_line(&mut rx, READY);
+        let old_pid = old.pid();
+
+        assert_eq!(old.stop(TIMEOUTS), ShutdownOutcome::Graceful);
+        let (mut rx, new) = start(&script);
+        wait_for_line(&mut rx, READY);
+
+        assert_ne!(new.pid(), old_pid);
+        assert!(new.is_alive());
+        assert!(!process_alive(old_pid), "old sidecar is still running");
+
+        new.stop(TIMEOUTS);
+        let _ = std::fs::remove_file(script);
+    }
+
+    #[test]
+    fn shutdown_reaps_within_grace_window() {
+        let script = write_script(ECHO_SIDECAR);
+        let (mut rx, child) = start(&script);
+        wait_for_line(&mut rx, READY);
+        let pid = child.pid();
+
+        let started = Instant::now();
+        assert_eq!(child.stop(TIMEOUTS), ShutdownOutcome::Graceful);
+        assert!(started.elapsed() < TIMEOUTS.grace);
+        assert!(!process_alive(pid));
+
+        let status = wait_for_exit_event(&mut rx);
+        assert_eq!(status.signal, Some(libc::SIGTERM));
+        let _ = std::fs::remove_file(script);
+    }
+
+    #[test]
+    fn ignoring_sigterm_escalates_to_kill() {
+        let script = write_script(STUBBORN_SIDECAR);
+        let (mut rx, child) = start(&script);
+        wait_for_line(&mut rx, READY);
+
+        let timeouts = StopTimeouts {
+            grace: Duration::from_millis(300),
+            hard: Duration::from_secs(2),
+        };
+        assert_eq!(child.stop(timeouts), ShutdownOutcome::ForcedAfterTimeout);
+
+        let status = wait_for_exit_event(&mut rx);
+        assert_eq!(status.signal, Some(libc::SIGKILL));
+        let _ = std::fs::remove_file(script);
+    }
+
+    #[test]
+    fn stopping_an_exited_process_reports_already_dead() {
+        let script = write_script("echo NULLGRAVITY_READY\n");
+        let (mut rx, child) = start(&script);
+        wait_for_exit_event(&mut rx);
+
+        assert_eq!(child.stop(TIMEOUTS), ShutdownOutcome::AlreadyDead);
+        let _ = std::fs::remove_file(script);
+    }
+}
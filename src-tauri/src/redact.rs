@@ -0,0 +1,116 @@
+use std::sync::Mutex;
+
+use regex::{Captures, Regex};
+
+use crate::sidecar::lock_recover;
+
+/// 命中的敏感内容替换成这个
+pub(crate) const MASK: &str = "[REDACTED]";
+
+/// 默认的脱敏规则；第一个捕获组（如果有）会原样保留，例如 `token=` 前缀
+///
+/// 键名允许带前缀（`OPENAI_API_KEY`、`DB_PASSWORD`），键和值都可以带引号（TOML、JSON、Python dict）。
+pub const DEFAULT_PATTERNS: &[&str] = &[
+    r#"(?i)\b((?:[a-z0-9_]*token|[a-z0-9_]*api[_-]?key|[a-z0-9_]*secret|[a-z0-9_]*password)["']?\s*[=:]\s*["']?)[^\s&,;'"]+"#,
+    r"(?i)\b(bearer\s+)[a-z0-9\-._~+/]+=*",
+    r"\b[0-9a-fA-F]{32,}\b",
+    r"[A-Za-z0-9+/_\-]{40,}={0,2}",
+];
+
+/// sidecar 输出在写日志、进缓冲区、推给前端之前统一经过这里
+#[derive(Default)]
+pub struct Redactor(Mutex<Vec<Regex>>);
+
+impl Redactor {
+    /// 替换规则，无法编译的规则记录警告后跳过
+    pub fn set_patterns(&self, patterns: &[String]) {
+        let compiled = patterns
+            .iter()
+            .filter_map(|pattern| match Regex::new(pattern) {
+                Ok(re) => Some(re),
+                Err(e) => {
+                    log::warn!("Ignoring invalid redaction pattern {:?}: {}", pattern, e);
+                    None
+                }
+            })
+            .collect();
+        *lock_recover(&self.0, "Redaction patterns") = compiled;
+    }
+
+    pub fn redact(&self, line: String) -> String {
+        let patterns = lock_recover(&self.0, "Redaction patterns");
+        let mut line = line;
+        for re in patterns.iter() {
+            if re.is_match(&line) {
+                line = re
+                    .replace_all(&line, |caps: &Captures| {
+                        let keep = caps.get(1).map_or("", |m| m.as_str());
+                        format!("{}{}", keep, MASK)
+                    })
+                    .into_owned();
+            }
+        }
+        line
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn redactor() -> Redactor {
+        let redactor = Redactor::default();
+        let patterns: Vec<String> = DEFAULT_PATTERNS.iter().map(|p| p.to_string()).collect();
+        redactor.set_patterns(&patterns);
+        redactor
+    }
+
+    #[test]
+    fn masks_plain_and_quoted_values() {
+        let r = redactor();
+        assert_eq!(r.redact("token=abc123".into()), "token=[REDACTED]");
+        assert_eq!(
+            r.redact(r#"password = "hunter2""#.into()),
+            r#"password = "[REDACTED]""#
+        );
+        assert_eq!(
+            r.redact(r#"{"api_key": "sk-abc", "user": "bob"}"#.into()),
+            r#"{"api_key": "[REDACTED]", "user": "bob"}"#
+        );
+        assert_eq!(
+            r.redact("{'token': 'abc', 'retries': 3}".into()),
+            "{'token': '[REDACTED]', 'retries': 3}"
+        );
+    }
+
+    #[test]
+    fn masks_prefixed_names() {
+        let r = redactor();
+        assert_eq!(
+            r.redact("OPENAI_API_KEY=sk-short".into()),
+            "OPENAI_API_KEY=[REDACTED]"
+        );
+        assert_eq!(
+            r.redact("DB_PASSWORD: pw".into()),
+            "DB_PASSWORD: [REDACTED]"
+        );
+        assert_eq!(
+            r.redact("client_secret=s3cr3t&next=1".into()),
+            "client_secret=[REDACTED]&next=1"
+        );
+    }
+
+    #[test]
+    fn leaves_ordinary_lines_alone() {
+        let r = redactor();
+        let line = "Listening on 127.0.0.1:8000, workers=4".to_string();
+        assert_eq!(r.redact(line.clone()), line);
+    }
+
+    #[test]
+    fn invalid_patterns_are_skipped() {
+        let r = Redactor::default();
+        r.set_patterns(&["(".to_string(), "secret".to_string()]);
+        assert_eq!(r.redact("a secret b".into()), "a [REDACTED] b");
+    }
+}
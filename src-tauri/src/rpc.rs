@@ -0,0 +1,253 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use log::debug;
+use serde::Serialize;
+use serde_json::{json, Value};
+use tauri::{AppHandle, Manager, State};
+use tokio::sync::oneshot;
+
+use crate::error::{CommandError, CommandResult};
+use crate::lifecycle::{self, ReadyState, SidecarEvent};
+use crate::sidecar::{self, lock_recover, Delivery, SidecarState};
+use crate::{READY_TIMEOUT, RPC_DEGRADED_AFTER, RPC_TIMEOUT};
+
+type Reply = Result<Value, Value>;
+
+/// 延迟统计覆盖最近这么多次 sidecar_call
+const LATENCY_WINDOW: usize = 500;
+
+/// 基于 stdin/stdout 的 JSON 行协议调用，按 id 关联请求与响应
+#[derive(Default)]
+pub struct RpcState {
+    next_id: AtomicU64,
+    pending: Mutex<HashMap<u64, oneshot::Sender<Reply>>>,
+    /// 连续超时的调用数，收到任何响应即清零
+    timeouts: AtomicU32,
+    /// 已发出 Degraded，尚未恢复
+    degraded: AtomicBool,
+    latency: Mutex<Latency>,
+}
+
+/// 前端发起的 sidecar_call 的往返耗时，sidecar 重启后清零
+#[derive(Default)]
+struct Latency {
+    recent: VecDeque<Duration>,
+    calls: u64,
+    timeouts: u64,
+}
+
+#[derive(Serialize)]
+pub struct RpcLatencyStats {
+    pub calls: u64,
+    pub timeouts: u64,
+    pub p50_ms: Option<f64>,
+    pub p95_ms: Option<f64>,
+    pub max_ms: Option<f64>,
+}
+
+impl Latency {
+    fn record(&mut self, elapsed: Option<Duration>) {
+        self.calls += 1;
+        let Some(elapsed) = elapsed else {
+            self.timeouts += 1;
+            return;
+        };
+        if self.recent.len() >= LATENCY_WINDOW {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(elapsed);
+    }
+
+    fn stats(&self) -> RpcLatencyStats {
+        let mut sorted: Vec<Duration> = self.recent.iter().copied().collect();
+        sorted.sort();
+        // 最近秩法：第 ceil(p * n) 个
+        let percentile = |p: f64| {
+            let rank = ((p * sorted.len() as f64).ceil() as usize).max(1);
+            sorted.get(rank - 1).map(|d| d.as_secs_f64() * 1000.0)
+        };
+        RpcLatencyStats {
+            calls: self.calls,
+            timeouts: self.timeouts,
+            p50_ms: percentile(0.5),
+            p95_ms: percentile(0.95),
+            max_ms: sorted.last().map(|d| d.as_secs_f64() * 1000.0),
+        }
+    }
+}
+
+impl RpcState {
+    fn forget(&self, id: u64) {
+        lock_recover(&self.pending, "RPC pending").remove(&id);
+    }
+
+    /// sidecar 退出时丢弃所有等待中的调用，调用方会立即收到错误
+    pub fn fail_all(&self) {
+        lock_recover(&self.pending, "RPC pending").clear();
+        self.timeouts.store(0, Ordering::SeqCst);
+        self.degraded.store(false, Ordering::SeqCst);
+        *lock_recover(&self.latency, "RPC latency") = Latency::default();
+    }
+}
+
+/// 收到响应：清零超时计数，之前判定为失联的话宣布恢复
+fn on_reply(app: &AppHandle) {
+    let rpc = app.state::<RpcState>();
+    rpc.timeouts.store(0, Ordering::SeqCst);
+    if rpc.degraded.swap(false, Ordering::SeqCst) {
+        log::info!("Sidecar is responding to calls again");
+        lifecycle::emit(app, SidecarEvent::Ready);
+    }
+}
+
+/// 连续超时达到阈值且进程仍在：进入 Degraded，先做一次心跳探测，探测也失败就重启
+///
+/// 未就绪期间的超时不计数，启动慢不等于失联。
+fn on_timeout(app: &AppHandle) {
+    if !app.state::<SidecarState>().is_ready() {
+        return;
+    }
+    let rpc = app.state::<RpcState>();
+    let timeouts = rpc.timeouts.fetch_add(1, Ordering::SeqCst) + 1;
+    if timeouts < RPC_DEGRADED_AFTER {
+        return;
+    }
+    let Some(pid) = sidecar::current_pid(app) else {
+        return;
+    };
+    if rpc.degraded.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    log::warn!(
+        "Sidecar PID={} is alive but {} calls in a row timed out",
+        pid,
+        timeouts
+    );
+    lifecycle::emit(app, SidecarEvent::Degraded { timeouts });
+    let handle = app.clone();
+    tauri::async_runtime::spawn(async move {
+        if crate::heartbeat::probe(&handle, pid).await {
+            log::info!(
+                "Sidecar PID={} still answers heartbeat, waiting for calls to recover",
+                pid
+            );
+            return;
+        }
+        log::warn!("Sidecar PID={} failed the health check, restarting", pid);
+        let restarted = crate::task::blocking(move || sidecar::restart(&handle).map(|_| ())).await;
+        if let Err(e) = restarted {
+            log::error!("Restart of degraded sidecar failed: {}", e);
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn sidecar_call(app: AppHandle, method: String, params: Value) -> CommandResult<Value> {
+    let started = Instant::now();
+    let result = call(&app, &method, params, RPC_TIMEOUT).await;
+    // 只统计拿到响应或超时的调用，sidecar 未运行时的立即失败不算
+    let elapsed = match &result {
+        Ok(_) | Err(CommandError::Backend(_)) => Some(Some(started.elapsed())),
+        Err(CommandError::Timeout) => Some(None),
+        Err(_) => None,
+    };
+    if let Some(elapsed) = elapsed {
+        lock_recover(&app.state::<RpcState>().latency, "RPC latency").record(elapsed);
+    }
+    result
+}
+
+/// 最近 sidecar_call 的 p50/p95/最大耗时，以及调用和超时次数
+#[tauri::command]
+pub fn rpc_latency_stats(state: State<RpcState>) -> RpcLatencyStats {
+    lock_recover(&state.latency, "RPC latency").stats()
+}
+
+pub async fn call(
+    app: &AppHandle,
+    method: &str,
+    params: Value,
+    timeout: Duration,
+) -> CommandResult<Value> {
+    let rpc = app.state::<RpcState>();
+    let id = rpc.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+    let (tx, rx) = oneshot::channel();
+    lock_recover(&rpc.pending, "RPC pending").insert(id, tx);
+
+    // 写 stdin 要拿 child 锁，重启期间可能等上几秒，不占用异步工作线程；未就绪时先排队
+    let frame = json!({ "id": id, "method": method, "params": params }).to_string();
+    let handle = app.clone();
+    let sent = frame.clone();
+    let delivery = match crate::task::blocking(move || sidecar::send(&handle, sent)).await {
+        Ok(delivery) => delivery,
+        Err(e) => {
+            rpc.forget(id);
+            return Err(e);
+        }
+    };
+    // 排队的帧要等 Ready 才写入，从那时起才开始计时
+    if delivery == Delivery::Queued {
+        if let Err(e) = app.state::<ReadyState>().wait(READY_TIMEOUT).await {
+            rpc.forget(id);
+            sidecar::unqueue(&app.state::<SidecarState>(), &frame);
+            log::warn!(
+                "Sidecar call '{}' gave up waiting for the sidecar to become ready",
+                method
+            );
+            return Err(e);
+        }
+    }
+
+    let reply = tokio::time::timeout(timeout, rx).await;
+    if let Ok(Ok(_)) = &reply {
+        on_reply(app);
+    }
+    match reply {
+        Ok(Ok(Ok(result))) => Ok(result),
+        Ok(Ok(Err(error))) => Err(CommandError::Backend(match error {
+            Value::String(message) => message,
+            other => other.to_string(),
+        })),
+        Ok(Err(_)) => Err(CommandError::SidecarNotRunning),
+        Err(_) => {
+            rpc.forget(id);
+            sidecar::unqueue(&app.state::<SidecarState>(), &frame);
+            log::warn!("Sidecar call '{}' timed out after {:?}", method, timeout);
+            on_timeout(app);
+            Err(CommandError::Timeout)
+        }
+    }
+}
+
+/// 尝试把一行 stdout 当作响应帧分发，是响应帧时返回 true
+pub fn dispatch(app: &AppHandle, line: &str) -> bool {
+    if !line.starts_with('{') {
+        return false;
+    }
+    let Ok(Value::Object(mut frame)) = serde_json::from_str::<Value>(line) else {
+        return false;
+    };
+    let Some(id) = frame.get("id").and_then(Value::as_u64) else {
+        return false;
+    };
+    let reply = match (frame.remove("result"), frame.remove("error")) {
+        (_, Some(error)) if !error.is_null() => Err(error),
+        (Some(result), _) => Ok(result),
+        (None, Some(_)) => Ok(Value::Null),
+        (None, None) => return false,
+    };
+
+    let rpc = app.state::<RpcState>();
+    let sender = lock_recover(&rpc.pending, "RPC pending").remove(&id);
+    match sender {
+        Some(tx) => {
+            let _ = tx.send(reply);
+        }
+        None => debug!("Dropping reply for unknown or expired call id={}", id),
+    }
+    true
+}
@@ -0,0 +1,292 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, TryLockError};
+use std::time::Duration;
+
+use log::{info, warn};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::config::SettingsState;
+use crate::error::{CommandError, CommandResult};
+use crate::process::{self, ExitStatus, ProcessEvent, SidecarChild};
+use crate::redact::Redactor;
+use crate::sidecar::{self, lock_recover, SidecarState, SIDECAR_NAME};
+use crate::task::blocking;
+
+/// 核心 sidecar 之外、按名字管理的附加后台进程，配置来自 settings.toml 的 `[services.<name>]`
+///
+/// 核心 sidecar 仍由 `SidecarState` 监管（自动启动、心跳、重启），这里的进程退出后不会自动拉起。
+#[derive(Default)]
+pub struct SidecarRegistry(Mutex<HashMap<String, SidecarChild>>);
+
+impl SidecarRegistry {
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, SidecarChild>> {
+        lock_recover(&self.0, "Sidecar registry")
+    }
+
+    pub fn pid(&self, name: &str) -> Option<u32> {
+        self.lock().get(name).map(SidecarChild::pid)
+    }
+}
+
+#[derive(Serialize)]
+pub struct ServiceInfo {
+    pub name: String,
+    pub pid: Option<u32>,
+    pub running: bool,
+    /// 是否为核心 sidecar
+    pub core: bool,
+}
+
+/// 附加进程退出时推给前端
+#[derive(Clone, Serialize)]
+struct ServiceExited {
+    name: String,
+    code: Option<i32>,
+    signal: Option<i32>,
+}
+
+fn core_name(app: &AppHandle) -> String {
+    app.state::<SettingsState>().get().sidecar.bin_name
+}
+
+#[tauri::command]
+pub async fn list_services(app: AppHandle) -> CommandResult<Vec<ServiceInfo>> {
+    blocking(move || Ok(collect_services(&app))).await
+}
+
+fn collect_services(app: &AppHandle) -> Vec<ServiceInfo> {
+    let registry = app.state::<SidecarRegistry>();
+    let core_pid = sidecar::current_pid(app);
+    let mut services = vec![ServiceInfo {
+        name: core_name(app),
+        pid: core_pid,
+        running: core_pid.is_some(),
+        core: true,
+    }];
+
+    let mut names: Vec<String> = app
+        .state::<SettingsState>()
+        .get()
+        .services
+        .into_keys()
+        .collect();
+    names.sort();
+    for name in names {
+        let pid = registry.pid(&name);
+        services.push(ServiceInfo {
+            name,
+            pid,
+            running: pid.is_some(),
+            core: false,
+        });
+    }
+    services
+}
+
+/// 启动指定服务，已在运行时直接返回其 PID
+#[tauri::command]
+pub async fn start_service(app: AppHandle, name: String) -> CommandResult<u32> {
+    blocking(move || start_by_name(&app, &name)).await
+}
+
+fn start_by_name(app: &AppHandle, name: &str) -> CommandResult<u32> {
+    if app.state::<SidecarState>().is_shutting_down() {
+        return Err(CommandError::ShuttingDown);
+    }
+    if name == core_name(app) {
+        return match sidecar::current_pid(app) {
+            Some(pid) => Ok(pid),
+            None => sidecar::spawn(app),
+        };
+    }
+    start(app, name)
+}
+
+#[tauri::command]
+pub async fn stop_service(app: AppHandle, name: String) -> CommandResult<()> {
+    if name == core_name(&app) {
+        return Err(CommandError::InvalidArgument(format!(
+            "'{}' is the core sidecar and is managed by the app",
+            name
+        )));
+    }
+    let child = app.state::<SidecarRegistry>().lock().remove(&name);
+    let Some(child) = child else {
+        return Err(CommandError::SidecarNotRunning);
+    };
+    // 优雅关闭会阻塞等待，放到阻塞线程池里跑
+    let handle = app.clone();
+    let grace = service_grace(&app, &name);
+    let outcome = blocking(move || Ok(sidecar::stop_child_with(&handle, child, grace))).await?;
+    info!("Service '{}' stopped: {:?}", name, outcome);
+    Ok(())
+}
+
+fn start(app: &AppHandle, name: &str) -> CommandResult<u32> {
+    let Some(mut config) = app.state::<SettingsState>().get().services.remove(name) else {
+        return Err(CommandError::InvalidArgument(format!(
+            "unknown service '{}'",
+            name
+        )));
+    };
+    // 没写 bin_name 时会落到核心 sidecar 的默认值，改用服务名
+    if config.bin_name == SIDECAR_NAME {
+        config.bin_name = name.to_string();
+    }
+
+    let registry = app.state::<SidecarRegistry>();
+    let mut children = registry.lock();
+    if let Some(child) = children.get(name) {
+        return Ok(child.pid());
+    }
+
+    let mut command = sidecar::command_for(app, &config)?;
+    // 附加进程通过环境变量找到核心 sidecar
+    if let Some(port) = *lock_recover(&app.state::<sidecar::ManagedPort>().0, "Managed port") {
+        command = command.env("NULLGRAVITY_PORT", port.to_string());
+    }
+    let (mut rx, child) =
+        process::spawn(command.into()).map_err(|e| CommandError::SpawnFailed(e.to_string()))?;
+    let pid = child.pid();
+    children.insert(name.to_string(), child);
+    info!("Service '{}' started, PID={}", name, pid);
+
+    let handle = app.clone();
+    let name = name.to_string();
+    let target = format!("sidecar:{}", name);
+    tauri::async_runtime::spawn(async move {
+        let mut exit = None;
+        while let Some(event) = rx.recv().await {
+            match event {
+                ProcessEvent::Stdout(bytes) => {
+                    let line = handle
+                        .state::<Redactor>()
+                        .redact(sidecar::decode_line(&bytes));
+                    info!(target: target.as_str(), "{}", line);
+                }
+                ProcessEvent::Stderr(bytes) => {
+                    let line = handle
+                        .state::<Redactor>()
+                        .redact(sidecar::decode_line(&bytes));
+                    warn!(target: target.as_str(), "{}", line);
+                }
+                ProcessEvent::Terminated(status) => {
+                    exit = Some(status);
+                    break;
+                }
+            }
+        }
+        on_exit(&handle, &name, pid, exit);
+    });
+    Ok(pid)
+}
+
+fn on_exit(app: &AppHandle, name: &str, pid: u32, exit: Option<ExitStatus>) {
+    warn!("Service '{}' PID={} terminated: {:?}", name, pid, exit);
+    let registry = app.state::<SidecarRegistry>();
+    let mut children = registry.lock();
+    if children.get(name).map(SidecarChild::pid) == Some(pid) {
+        children.remove(name);
+    }
+    drop(children);
+    let _ = app.emit(
+        "service-exited",
+        ServiceExited {
+            name: name.to_string(),
+            code: exit.and_then(|s| s.code),
+            signal: exit.and_then(|s| s.signal),
+        },
+    );
+}
+
+/// 服务自己配置的 grace_ms，没有时用全局值
+fn service_grace(app: &AppHandle, name: &str) -> Duration {
+    let settings = app.state::<SettingsState>().get();
+    settings
+        .services
+        .get(name)
+        .and_then(|config| config.grace_ms)
+        .map(Duration::from_millis)
+        .unwrap_or_else(|| settings.grace())
+}
+
+/// `stop_order` 中的服务排在前面，其余按名字排序
+fn shutdown_sequence(mut names: Vec<String>, stop_order: &[String]) -> Vec<String> {
+    names.sort_by_key(|name| {
+        let rank = stop_order.iter().position(|n| n == name);
+        (rank.unwrap_or(usize::MAX), name.clone())
+    });
+    names
+}
+
+/// 按 `stop_order` 逐个关闭附加进程，各自使用自己的 grace；随核心 sidecar 的关闭流程一起调用，核心 sidecar 最后关闭
+///
+/// 强杀后仍报告卡死的进程由 `sidecar::stop_child_with` 转入后台重试，不阻塞后面的服务。
+pub fn stop_all(app: &AppHandle) {
+    let mut children: HashMap<String, SidecarChild> =
+        app.state::<SidecarRegistry>().lock().drain().collect();
+    if children.is_empty() {
+        return;
+    }
+    let stop_order = app.state::<SettingsState>().get().stop_order;
+    let sequence = shutdown_sequence(children.keys().cloned().collect(), &stop_order);
+    info!("Stopping services in order: {:?}", sequence);
+
+    for (step, name) in sequence.into_iter().enumerate() {
+        let Some(child) = children.remove(&name) else {
+            continue;
+        };
+        let grace = service_grace(app, &name);
+        info!(
+            "Stopping service '{}' (step {}, PID={}, grace {:?})",
+            name,
+            step + 1,
+            child.pid(),
+            grace
+        );
+        let outcome = sidecar::stop_child_with(app, child, grace);
+        info!("Service '{}' stopped: {:?}", name, outcome);
+    }
+}
+
+/// panic 路径：不等锁、不等退出
+pub fn kill_all_now(app: &AppHandle) {
+    let registry = app.state::<SidecarRegistry>();
+    let guard = match registry.0.try_lock() {
+        Ok(guard) => Some(guard),
+        Err(TryLockError::Poisoned(poisoned)) => Some(poisoned.into_inner()),
+        Err(TryLockError::WouldBlock) => None,
+    };
+    if let Some(mut children) = guard {
+        for (_, child) in children.drain() {
+            child.kill();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(list: &[&str]) -> Vec<String> {
+        list.iter().map(|n| n.to_string()).collect()
+    }
+
+    #[test]
+    fn listed_services_stop_first_then_the_rest_by_name() {
+        let running = names(&["worker", "indexer", "cache", "api"]);
+        // "ghost" 写在 stop_order 里但没有运行，不应出现在结果中
+        let order = names(&["indexer", "ghost", "api"]);
+        assert_eq!(
+            shutdown_sequence(running, &order),
+            ["indexer", "api", "cache", "worker"]
+        );
+    }
+
+    #[test]
+    fn without_stop_order_services_stop_by_name() {
+        let running = names(&["worker", "cache"]);
+        assert_eq!(shutdown_sequence(running, &[]), ["cache", "worker"]);
+    }
+}
@@ -0,0 +1,133 @@
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use tauri::{AppHandle, Manager};
+
+use crate::config::SettingsState;
+use crate::error::CommandResult;
+use crate::output::LogBuffer;
+use crate::sidecar::lock_recover;
+use crate::task::blocking;
+
+/// 本次运行的 sidecar 输出落盘到日志目录下的这个文件，下次启动时改名为 PREVIOUS_SESSION_FILE
+const LAST_SESSION_FILE: &str = "last-session.log";
+const PREVIOUS_SESSION_FILE: &str = "previous-session.log";
+/// 每隔这么久把新增的行追加进文件
+const SESSION_FLUSH_INTERVAL: Duration = Duration::from_secs(3);
+/// 文件超过这个大小后用内存中的缓冲区整个重写一次，让它始终只比环形缓冲区略大
+const SESSION_MAX_BYTES: u64 = 512 * 1024;
+
+/// 环形缓冲区的落盘进度，只在开启 `persist_session_log` 时有 writer
+#[derive(Default)]
+pub struct SessionLog(Mutex<Option<Writer>>);
+
+struct Writer {
+    path: PathBuf,
+    file: File,
+    /// 已落盘的累计行数，对应 `LogBuffer::since` 返回的计数
+    flushed: u64,
+    bytes: u64,
+}
+
+impl Writer {
+    /// 一批只写一次；`rewrite` 时先清空文件
+    fn write(&mut self, lines: &[String], total: u64, rewrite: bool) -> std::io::Result<()> {
+        if rewrite {
+            self.file = File::create(&self.path)?;
+            self.bytes = 0;
+        }
+        let mut batch = String::new();
+        for line in lines {
+            batch.push_str(line);
+            if !line.ends_with('\n') {
+                batch.push('\n');
+            }
+        }
+        self.file.write_all(batch.as_bytes())?;
+        self.bytes += batch.len() as u64;
+        self.flushed = total;
+        Ok(())
+    }
+}
+
+/// setup 中调用：把上次的 last-session.log 改名为 previous-session.log，开启时开始定期落盘
+pub fn start(app: &AppHandle) {
+    let dir = match crate::paths::log_dir(app) {
+        Ok(dir) => dir,
+        Err(e) => {
+            log::warn!("Session log disabled, no log directory: {}", e);
+            return;
+        }
+    };
+    let path = dir.join(LAST_SESSION_FILE);
+    if path.exists() {
+        if let Err(e) = std::fs::rename(&path, dir.join(PREVIOUS_SESSION_FILE)) {
+            log::warn!("Failed to keep the previous session log: {}", e);
+        }
+    }
+    if !app.state::<SettingsState>().get().persist_session_log {
+        return;
+    }
+
+    let file = match File::create(&path) {
+        Ok(file) => file,
+        Err(e) => {
+            log::warn!("Failed to create {}: {}", path.display(), e);
+            return;
+        }
+    };
+    *lock_recover(&app.state::<SessionLog>().0, "Session log") = Some(Writer {
+        path,
+        file,
+        flushed: 0,
+        bytes: 0,
+    });
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(SESSION_FLUSH_INTERVAL).await;
+            let handle = app.clone();
+            let _ = blocking(move || {
+                flush(&handle);
+                Ok(())
+            })
+            .await;
+        }
+    });
+}
+
+/// 只追加上次落盘之后的新行；崩溃处理时也会调用一次
+pub fn flush(app: &AppHandle) {
+    let session = app.state::<SessionLog>();
+    let mut guard = lock_recover(&session.0, "Session log");
+    let Some(writer) = guard.as_mut() else {
+        return;
+    };
+    let rewrite = writer.bytes >= SESSION_MAX_BYTES;
+    let seen = if rewrite { 0 } else { writer.flushed };
+    let (lines, total) = app.state::<LogBuffer>().since(seen);
+    if lines.is_empty() {
+        return;
+    }
+    if let Err(e) = writer.write(&lines, total, rewrite) {
+        log::warn!("Failed to write {}: {}", writer.path.display(), e);
+    }
+}
+
+/// 上一次运行落盘的 sidecar 输出尾部，供崩溃报告附带；上次没有开启时为 None
+#[tauri::command]
+pub async fn previous_session_log(app: AppHandle) -> CommandResult<Option<String>> {
+    blocking(move || {
+        let path = crate::paths::log_dir(&app)?.join(PREVIOUS_SESSION_FILE);
+        match std::fs::read(&path) {
+            Ok(bytes) => Ok(Some(String::from_utf8_lossy(&bytes).into_owned())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    })
+    .await
+}
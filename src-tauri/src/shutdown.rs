@@ -0,0 +1,141 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::Duration;
+
+use log::{info, warn};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::error::CommandResult;
+use crate::sidecar;
+
+/// 发出 quit-requested 后等前端答复的上限，前端卡死或没有处理时照常退出
+const QUIT_GUARD_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 退出流程只走一次，重复触发（托盘、信号、command 同时到达）直接忽略
+#[derive(Default)]
+pub struct QuitState(AtomicBool);
+
+impl QuitState {
+    pub fn is_quitting(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+/// 前端有未保存内容时打开：关闭主窗口先问前端，由前端调用 quit_app 确认或 cancel_quit 取消
+#[derive(Default)]
+pub struct QuitGuard {
+    enabled: AtomicBool,
+    /// 正在等待答复的请求编号，0 表示没有；超时任务据此判断请求是否已被答复
+    pending: AtomicU64,
+    next_request: AtomicU64,
+}
+
+impl QuitGuard {
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::SeqCst)
+    }
+}
+
+#[derive(Clone, Serialize)]
+struct QuitRequested {
+    timeout_ms: u64,
+}
+
+#[tauri::command]
+pub fn set_quit_guard(state: State<QuitGuard>, enabled: bool) {
+    state.enabled.store(enabled, Ordering::SeqCst);
+    info!(
+        "Quit guard {}",
+        if enabled { "enabled" } else { "disabled" }
+    );
+}
+
+/// 拦下一次关闭，推送 `quit-requested` 事件；前端迟迟不答复就在 QUIT_GUARD_TIMEOUT 后直接退出
+///
+/// 已有请求在等答复时不重复推送。
+pub fn request_quit(app: &AppHandle) {
+    let guard = app.state::<QuitGuard>();
+    let id = guard.next_request.fetch_add(1, Ordering::SeqCst) + 1;
+    if guard
+        .pending
+        .compare_exchange(0, id, Ordering::SeqCst, Ordering::SeqCst)
+        .is_err()
+    {
+        return;
+    }
+    info!("Quit guard is on, asking the frontend to confirm");
+    let _ = app.emit(
+        "quit-requested",
+        QuitRequested {
+            timeout_ms: QUIT_GUARD_TIMEOUT.as_millis() as u64,
+        },
+    );
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(QUIT_GUARD_TIMEOUT).await;
+        let answered = app
+            .state::<QuitGuard>()
+            .pending
+            .compare_exchange(id, 0, Ordering::SeqCst, Ordering::SeqCst)
+            .is_err();
+        if !answered {
+            warn!(
+                "Frontend did not answer quit-requested within {:?}, quitting anyway",
+                QUIT_GUARD_TIMEOUT
+            );
+            quit(&app, "quit guard timeout");
+        }
+    });
+}
+
+/// 用户在确认框里选择留下
+#[tauri::command]
+pub fn cancel_quit(state: State<QuitGuard>) {
+    if state.pending.swap(0, Ordering::SeqCst) != 0 {
+        info!("Quit was cancelled by the frontend");
+    }
+}
+
+/// 整个应用的退出路径：先优雅关闭 sidecar，再结束事件循环
+///
+/// 会阻塞直到 sidecar 退出或超时，不要在主线程调用。
+pub fn quit_blocking(app: &AppHandle, reason: &str) {
+    if app.state::<QuitState>().0.swap(true, Ordering::SeqCst) {
+        info!("Quit already in progress, ignoring {}", reason);
+        return;
+    }
+    info!("Quitting application ({})", reason);
+    let outcome = sidecar::shutdown_sidecar(app);
+    info!("Sidecar shutdown on {}: {:?}", reason, outcome);
+    app.exit(0);
+}
+
+/// 在后台线程执行 quit_blocking
+pub fn quit(app: &AppHandle, reason: &'static str) {
+    let app = app.clone();
+    std::thread::spawn(move || quit_blocking(&app, reason));
+}
+
+/// 优雅关闭 sidecar 后退出整个应用，与关闭到托盘不同
+#[tauri::command]
+pub async fn quit_app(app: AppHandle) -> CommandResult<()> {
+    // 同时也是对 quit-requested 的确认
+    app.state::<QuitGuard>().pending.store(0, Ordering::SeqCst);
+    crate::task::blocking(move || {
+        quit_blocking(&app, "quit_app command");
+        Ok(())
+    })
+    .await
+}
+
+/// 终端里 Ctrl-C / kill 时走同样的关闭流程，Windows 下对应控制台关闭、注销事件
+pub fn install_signal_handler(app: &AppHandle) {
+    let app = app.clone();
+    if let Err(e) = ctrlc::set_handler(move || {
+        info!("Shutdown triggered by signal");
+        quit_blocking(&app, "signal");
+    }) {
+        warn!("Failed to install signal handler: {}", e);
+    }
+}
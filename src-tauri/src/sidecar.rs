@@ -0,0 +1,1569 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::path::Path;
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+use tauri::ipc::CapabilityBuilder;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+use tauri_plugin_shell::ShellExt;
+
+use crate::breaker::CircuitBreaker;
+use crate::config::{Settings, SettingsState, SidecarConfig};
+use crate::controller::{OsSpawner, Release, SidecarController, Started};
+use crate::error::{CommandError, CommandResult};
+use crate::i18n::{self, tr, Msg};
+use crate::lifecycle::{self, SidecarEvent};
+use crate::logging::SIDECAR_TARGET;
+use crate::output::ChunkAssembler;
+use crate::port;
+pub use crate::process::ShutdownOutcome;
+use crate::process::{
+    self, Events, ExitStatus, OutputMode, ProcessEvent, SidecarChild, StopTimeouts,
+};
+use crate::redact::Redactor;
+use crate::task::blocking;
+use crate::{
+    EXTERNAL_PROBE_INTERVAL, MAX_RESPAWN_ATTEMPTS, OUTBOUND_QUEUE_LIMIT, PIPE_CHECK_DELAY,
+    PORT_RETRY_LIMIT, READY_PROBE_INTERVAL, READY_TIMEOUT, STARTUP_RETRY_DELAY,
+    STARTUP_SPAWN_ATTEMPTS, STUCK_KILL_INTERVAL, STUCK_KILL_RETRIES,
+};
+
+pub const SIDECAR_NAME: &str = "nullgravity-core";
+
+/// 调试构建额外传给 sidecar 的参数：打开后端开发模式和详细日志，发布构建从不传
+const DEV_ARGS: &[&str] = &["--dev", "--verbose"];
+
+/// 后端 sidecar 的运行状态
+#[derive(Default)]
+pub struct SidecarState {
+    /// 进程句柄、就绪、重启退避等监督状态
+    controller: SidecarController<OsSpawner>,
+    /// 已报告过当前进程的 stdin 管道断开，新进程起来后复位
+    pipe_broken: AtomicBool,
+    /// sidecar 报告端口被占用，退出后换端口立即重启而不是按崩溃处理
+    port_conflict: AtomicBool,
+    /// 连续因端口冲突重启的次数，就绪后清零
+    port_retries: AtomicU32,
+    /// 就绪前前端发来的消息，Ready 时按顺序写入 stdin
+    outbound: Mutex<VecDeque<String>>,
+    /// 崩溃过于频繁时打开，打开期间不再自动重启
+    breaker: CircuitBreaker,
+    /// 这一轮连续崩溃已经弹过对话框，就绪后复位；崩溃循环时只弹一次
+    crash_dialog_shown: AtomicBool,
+    /// 启动重试用尽时的失败原因，之后任意一次启动成功即清空
+    start_error: Mutex<Option<String>>,
+    /// 使用 `--external-sidecar` 指定的外部后端，进程不归我们管
+    external: AtomicBool,
+    /// 当前进程启动时实际使用的命令行，spawn 成功时更新
+    launch: Mutex<Option<LaunchInfo>>,
+    /// 仍在重试强杀卡死进程的后台线程
+    reapers: Mutex<Vec<std::thread::JoinHandle<()>>>,
+}
+
+/// 加锁时从 PoisonError 中恢复：某个线程 panic 不应该让后续所有操作跟着崩
+pub(crate) fn lock_recover<'a, T>(mutex: &'a Mutex<T>, what: &str) -> MutexGuard<'a, T> {
+    mutex.lock().unwrap_or_else(|poisoned| {
+        warn!("{} lock was poisoned, recovering", what);
+        poisoned.into_inner()
+    })
+}
+
+impl SidecarState {
+    pub fn lock_child(&self) -> MutexGuard<'_, Option<SidecarChild>> {
+        self.controller.lock_child()
+    }
+
+    /// 当前进程已运行的时长
+    pub fn uptime(&self) -> Option<Duration> {
+        self.controller.uptime()
+    }
+
+    pub fn begin_shutdown(&self) {
+        self.controller.begin_shutdown();
+    }
+
+    /// 决定 watchdog 如何处理一次退出，会消费 intentional_stop 与 port_conflict 标记
+    fn classify_exit(&self, exit: &Option<ExitStatus>) -> ExitAction {
+        if self.is_shutting_down() {
+            return ExitAction::Ignore;
+        }
+        if self.controller.take_intentional_stop() {
+            info!("Sidecar exit was requested, not treating it as a crash");
+            return ExitAction::Ignore;
+        }
+        if self.port_conflict.swap(false, Ordering::SeqCst) {
+            return ExitAction::NewPort;
+        }
+        ExitAction::Respawn {
+            crashed: is_crash(exit),
+        }
+    }
+
+    pub fn is_shutting_down(&self) -> bool {
+        self.controller.is_shutting_down()
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.controller.is_ready()
+    }
+
+    pub fn is_external(&self) -> bool {
+        self.external.load(Ordering::SeqCst)
+    }
+
+    /// 启动、重启、发信号这类需要拥有进程的操作，外部后端一律拒绝
+    pub(crate) fn ensure_owned(&self) -> CommandResult<()> {
+        if self.is_external() {
+            return Err(CommandError::Unsupported(
+                "the sidecar is an external process, manage it yourself".into(),
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn start_error(&self) -> Option<String> {
+        lock_recover(&self.start_error, "Sidecar start error").clone()
+    }
+
+    fn set_start_error(&self, error: Option<String>) {
+        *lock_recover(&self.start_error, "Sidecar start error") = error;
+    }
+
+    /// 用户显式重试时闭合熔断器
+    fn close_breaker(&self) {
+        if self.breaker.is_open() {
+            info!("Closing the sidecar circuit breaker on explicit retry");
+        }
+        self.breaker.reset();
+        self.crash_dialog_shown.store(false, Ordering::SeqCst);
+    }
+}
+
+/// spawn 失败的原因分类，前端据此给出针对性的帮助
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", content = "message")]
+pub enum SpawnFailureKind {
+    /// 可执行文件缺失，提示重新安装
+    BinaryNotFound,
+    /// 没有执行权限或被拦截，提示检查杀毒软件
+    PermissionDenied,
+    Io(String),
+}
+
+impl SpawnFailureKind {
+    pub fn classify(error: &std::io::Error) -> Self {
+        match error.kind() {
+            std::io::ErrorKind::NotFound => Self::BinaryNotFound,
+            std::io::ErrorKind::PermissionDenied => Self::PermissionDenied,
+            _ => Self::Io(error.to_string()),
+        }
+    }
+
+    /// spawn 之前的检查失败：可执行文件的问题重新探测一次拿到具体原因，其余按原样归为 Io
+    fn of_command_error(bin_name: &str, error: &CommandError) -> Self {
+        match error {
+            CommandError::BinaryUnusable(_) => crate::binary::path(bin_name)
+                .and_then(|path| crate::binary::probe(&path))
+                .err()
+                .map_or(Self::BinaryNotFound, |e| Self::classify(&e)),
+            other => Self::Io(other.to_string()),
+        }
+    }
+}
+
+/// 最近一次启动 sidecar 失败的原因，成功启动后为 None
+#[tauri::command]
+pub fn last_spawn_error(state: State<SidecarState>) -> Option<SpawnFailureKind> {
+    state.controller.last_spawn_error()
+}
+
+/// 判定 sidecar 就绪的方式
+///
+/// settings.toml 中写作 `readiness = { sentinel = "NULLGRAVITY_READY" }` 或 `readiness = "port_probe"`。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReadinessMode {
+    /// stdout 出现包含该字符串的行
+    Sentinel(String),
+    /// 能连上分配的端口，适合不打印就绪标记的 HTTP 后端
+    PortProbe,
+}
+
+/// watchdog 看到进程退出后的处理方式
+#[derive(Debug, PartialEq, Eq)]
+enum ExitAction {
+    /// 关闭或重启时主动停止的进程
+    Ignore,
+    /// 端口冲突，换端口立即重启
+    NewPort,
+    /// 按退避重启，`crashed` 时先发 Crashed 并提示用户
+    Respawn { crashed: bool },
+}
+
+/// 分配给 sidecar 的本地端口
+pub struct ManagedPort(pub Mutex<Option<u16>>);
+
+impl ManagedPort {
+    pub fn new(port: u16) -> Self {
+        Self(Mutex::new(Some(port)))
+    }
+}
+
+#[tauri::command]
+pub fn sidecar_port(state: State<ManagedPort>) -> Option<u16> {
+    *lock_recover(&state.0, "Managed port")
+}
+
+/// sidecar 的实际启动方式，排查环境问题用；参数和环境变量都经过脱敏
+#[derive(Clone, Serialize)]
+pub struct LaunchInfo {
+    pub pid: u32,
+    pub bin_name: String,
+    /// 解析后的可执行文件路径
+    pub program: String,
+    pub args: Vec<String>,
+    pub cwd: Option<String>,
+    /// 在继承的环境之上显式设置的变量，`null` 表示被移除
+    pub env_overrides: BTreeMap<String, Option<String>>,
+}
+
+impl LaunchInfo {
+    /// 在 spawn 之前记录，pid 待进程起来后补上
+    fn capture(command: &Command, bin_name: &str, redactor: &Redactor) -> Self {
+        let lossy = |s: &std::ffi::OsStr| s.to_string_lossy().into_owned();
+        let cwd = command
+            .get_current_dir()
+            .map(Path::to_path_buf)
+            .or_else(|| std::env::current_dir().ok());
+        let env_overrides = command
+            .get_envs()
+            .map(|(key, value)| {
+                let key = lossy(key);
+                let value = value.map(|value| {
+                    // 带上变量名一起脱敏，`API_TOKEN=...` 这类规则才能命中
+                    let pair = redactor.redact(format!("{}={}", key, lossy(value)));
+                    match pair.strip_prefix(&format!("{}=", key)) {
+                        Some(value) => value.to_string(),
+                        None => pair,
+                    }
+                });
+                (key, value)
+            })
+            .collect();
+        Self {
+            pid: 0,
+            bin_name: bin_name.to_string(),
+            program: lossy(command.get_program()),
+            args: command
+                .get_args()
+                .map(|arg| redactor.redact(lossy(arg)))
+                .collect(),
+            cwd: cwd.map(|dir| dir.to_string_lossy().into_owned()),
+            env_overrides,
+        }
+    }
+}
+
+/// 当前 sidecar 的启动命令行，未运行时返回 SidecarNotRunning
+#[tauri::command]
+pub async fn sidecar_launch_info(app: AppHandle) -> CommandResult<LaunchInfo> {
+    blocking(move || {
+        let pid = current_pid(&app).ok_or(CommandError::SidecarNotRunning)?;
+        let state = app.state::<SidecarState>();
+        lock_recover(&state.launch, "Sidecar launch info")
+            .clone()
+            .filter(|info| info.pid == pid)
+            .ok_or(CommandError::SidecarNotRunning)
+    })
+    .await
+}
+
+/// 前端连接指示用的进程状态
+#[derive(Serialize)]
+pub struct SidecarStatus {
+    pub running: bool,
+    pub pid: Option<u32>,
+    /// 启动失败时的原因，前端据此显示“后端启动失败 — 重试”
+    pub start_error: Option<String>,
+    /// 崩溃熔断已打开，需要 retry_start_sidecar 才会再启动
+    pub circuit_open: bool,
+    /// 外部后端：`running` 表示端口能连上，`pid` 始终为空
+    pub external: bool,
+}
+
+pub fn current_pid(app: &AppHandle) -> Option<u32> {
+    app.state::<SidecarState>().controller.pid()
+}
+
+/// 重启期间 child 锁可能被持有数秒，不能在主线程上等
+#[tauri::command]
+pub async fn sidecar_status(app: AppHandle) -> CommandResult<SidecarStatus> {
+    blocking(move || {
+        let state = app.state::<SidecarState>();
+        let status = state.controller.status();
+        Ok(SidecarStatus {
+            running: if state.is_external() {
+                status.ready
+            } else {
+                status.running
+            },
+            pid: status.pid,
+            start_error: state.start_error(),
+            circuit_open: state.breaker.is_open(),
+            external: state.is_external(),
+        })
+    })
+    .await
+}
+
+/// 清理上次异常退出遗留的 sidecar 进程，只匹配精确的可执行文件名
+pub fn cleanup_orphans(bin_name: &str) {
+    use sysinfo::{ProcessRefreshKind, ProcessesToUpdate, System, UpdateKind};
+
+    let mut sys = System::new();
+    sys.refresh_processes_specifics(
+        ProcessesToUpdate::All,
+        true,
+        ProcessRefreshKind::nothing().with_exe(UpdateKind::Always),
+    );
+
+    let own_pid = std::process::id();
+    for (pid, process) in sys.processes() {
+        if pid.as_u32() == own_pid {
+            continue;
+        }
+        // Linux 的进程名会截断到 15 个字符，优先看 exe 路径
+        let matches = match process.exe().and_then(|p| p.file_stem()) {
+            Some(stem) => stem == bin_name,
+            None => {
+                let name = process.name().to_string_lossy();
+                name == bin_name || name.strip_suffix(".exe") == Some(bin_name)
+            }
+        };
+        if matches {
+            warn!("Killing orphaned sidecar PID={}", pid);
+            process.kill();
+        }
+    }
+}
+
+/// 向 sidecar stdin 写入一行，构成最简单的行协议
+#[tauri::command]
+pub async fn sidecar_send(app: AppHandle, line: String) -> CommandResult<()> {
+    blocking(move || send(&app, line)).await.map(|_| ())
+}
+
+/// 前端消息的统一入口：在 `send_or_queue` 之上处理管道断开
+pub fn send(app: &AppHandle, line: String) -> CommandResult<Delivery> {
+    let result = send_or_queue(&app.state::<SidecarState>(), line);
+    if let Err(CommandError::PipeBroken) = result {
+        on_pipe_broken(app);
+    }
+    result
+}
+
+/// stdin 管道断开：通知前端停止发送，稍后确认进程死活；进程还在却已收不到指令时重启
+///
+/// 进程确实退出的话由 watchdog 照常处理，这里不插手。
+fn on_pipe_broken(app: &AppHandle) {
+    if app
+        .state::<SidecarState>()
+        .pipe_broken
+        .swap(true, Ordering::SeqCst)
+    {
+        return;
+    }
+    warn!("Sidecar stdin pipe is broken, checking whether the process is still alive");
+    lifecycle::emit(app, SidecarEvent::PipeBroken);
+
+    let handle = app.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(PIPE_CHECK_DELAY).await;
+        let result = blocking(move || {
+            let state = handle.state::<SidecarState>();
+            // 只看仍是断管的那个进程，期间 watchdog 已换上的新进程不受影响
+            let stuck = state
+                .lock_child()
+                .as_ref()
+                .filter(|c| c.stdin_closed() && c.is_alive())
+                .map(|c| c.pid());
+            match stuck {
+                Some(pid) => {
+                    warn!("Sidecar PID={} is alive with a dead stdin, restarting", pid);
+                    restart(&handle).map(|_| ())
+                }
+                None => Ok(()),
+            }
+        })
+        .await;
+        if let Err(e) = result {
+            error!("Restart after broken stdin pipe failed: {}", e);
+        }
+    });
+}
+
+/// `send_or_queue` 对一条消息的处理结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Delivery {
+    Written,
+    /// 已排队，Ready 时才写入 stdin
+    Queued,
+}
+
+/// 前端发来的消息：已就绪直接写入，否则排队等 Ready，队列满时报错
+///
+/// 就绪后队列里还留着上次没写出去的消息时先按顺序补写，保证先到的消息先写。
+pub fn send_or_queue(state: &SidecarState, line: String) -> CommandResult<Delivery> {
+    if state.is_external() {
+        return Err(CommandError::Unsupported(
+            "an external sidecar has no stdin to write to".into(),
+        ));
+    }
+    let mut queue = lock_recover(&state.outbound, "Sidecar outbound queue");
+    if state.is_ready() {
+        // 上次 flush 写失败留下的消息先补写，否则队列永远不会清空
+        drain(state, &mut queue)?;
+        return write_line(state, &line).map(|()| Delivery::Written);
+    }
+    if state.is_shutting_down() {
+        return Err(CommandError::ShuttingDown);
+    }
+    if queue.len() >= OUTBOUND_QUEUE_LIMIT {
+        return Err(CommandError::QueueFull);
+    }
+    queue.push_back(line);
+    Ok(Delivery::Queued)
+}
+
+/// 撤回一条还在排队的消息，比如等待它的调用已经放弃；已写入的返回 false
+pub fn unqueue(state: &SidecarState, line: &str) -> bool {
+    let mut queue = lock_recover(&state.outbound, "Sidecar outbound queue");
+    match queue.iter().position(|queued| queued == line) {
+        Some(index) => {
+            queue.remove(index);
+            true
+        }
+        None => false,
+    }
+}
+
+/// 就绪后把排队的消息按顺序写入，写失败的留到下一次 send_or_queue 再补写
+fn flush_outbound(state: &SidecarState) {
+    let mut queue = lock_recover(&state.outbound, "Sidecar outbound queue");
+    if !queue.is_empty() {
+        info!(
+            "Sending {} message(s) queued before the sidecar was ready",
+            queue.len()
+        );
+    }
+    if let Err(e) = drain(state, &mut queue) {
+        warn!("Failed to flush queued sidecar message: {}", e);
+    }
+}
+
+/// 按顺序写出队列里的消息，遇到写失败就停下，剩余的保持原样
+fn drain(state: &SidecarState, queue: &mut VecDeque<String>) -> CommandResult<()> {
+    while let Some(line) = queue.front() {
+        write_line(state, line)?;
+        queue.pop_front();
+    }
+    Ok(())
+}
+
+pub fn write_line(state: &SidecarState, line: &str) -> CommandResult<()> {
+    let mut guard = state.lock_child();
+    let child = guard.as_mut().ok_or(CommandError::SidecarNotRunning)?;
+    let mut buf = Vec::with_capacity(line.len() + 1);
+    buf.extend_from_slice(line.as_bytes());
+    buf.push(b'\n');
+    child.write(&buf).map_err(|e| match e.kind() {
+        std::io::ErrorKind::BrokenPipe => CommandError::PipeBroken,
+        _ => CommandError::Io(e.to_string()),
+    })
+}
+
+/// 向 sidecar 发送白名单内的信号，例如 "SIGHUP" 让后端不重启就重新加载配置；可省略 "SIG" 前缀
+#[tauri::command]
+pub async fn send_signal(app: AppHandle, sig: String) -> CommandResult<()> {
+    let name = sig.trim().to_ascii_uppercase();
+    let name = if name.starts_with("SIG") {
+        name
+    } else {
+        format!("SIG{}", name)
+    };
+    if !process::ALLOWED_SIGNALS.contains(&name.as_str()) {
+        return Err(CommandError::InvalidArgument(format!(
+            "signal '{}' is not allowed, expected one of {:?}",
+            sig,
+            process::ALLOWED_SIGNALS
+        )));
+    }
+    blocking(move || {
+        app.state::<SidecarState>().ensure_owned()?;
+        let pid = current_pid(&app).ok_or(CommandError::SidecarNotRunning)?;
+        process::send_signal(pid, &name).map_err(|e| match e.kind() {
+            std::io::ErrorKind::Unsupported => CommandError::Unsupported(e.to_string()),
+            _ => CommandError::Io(e.to_string()),
+        })?;
+        info!("Sent {} to sidecar PID={}", name, pid);
+        Ok(())
+    })
+    .await
+}
+
+/// 启动 sidecar 并挂上 watchdog，返回新进程 PID
+///
+/// 已经在运行时不重复启动，直接返回当前 PID。
+pub fn spawn(app: &AppHandle) -> CommandResult<u32> {
+    let state = app.state::<SidecarState>();
+    state.ensure_owned()?;
+    match state.controller.start(|| prepare(app, &state))? {
+        Some(started) => Ok(wire(app, &state, started)),
+        None => current_pid(app).ok_or(CommandError::SidecarNotRunning),
+    }
+}
+
+/// 启动失败或熔断后由前端的错误页发起重试，同时闭合熔断器；已在运行时直接返回 PID
+#[tauri::command]
+pub async fn retry_start_sidecar(app: AppHandle) -> CommandResult<u32> {
+    blocking(move || {
+        let state = app.state::<SidecarState>();
+        if state.is_shutting_down() {
+            return Err(CommandError::ShuttingDown);
+        }
+        state.ensure_owned()?;
+        state.close_breaker();
+        if let Some(pid) = current_pid(&app) {
+            return Ok(pid);
+        }
+        state.controller.reset_respawns();
+        let result = spawn(&app);
+        if let Err(e) = &result {
+            warn!("Retrying sidecar start failed: {}", e);
+            state.set_start_error(Some(e.to_string()));
+            lifecycle::emit(&app, SidecarEvent::Stopped);
+        }
+        result
+    })
+    .await
+}
+
+/// 首次启动：失败时按递增间隔重试，用尽后弹窗让用户选择退出或继续
+///
+/// 在后台任务中运行，不阻塞 setup 返回。
+pub fn spawn_with_retry(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut last_error = String::new();
+        let mut missing = false;
+        for attempt in 1..=STARTUP_SPAWN_ATTEMPTS {
+            match spawn(&app) {
+                Ok(_) => return,
+                // 文件缺失重试也没用，直接告诉用户去哪里找
+                Err(CommandError::BinaryUnusable(e)) => {
+                    error!("{}", e);
+                    last_error = e;
+                    missing = true;
+                    break;
+                }
+                Err(e) => {
+                    warn!(
+                        "Initial sidecar spawn failed (attempt {}/{}): {}",
+                        attempt, STARTUP_SPAWN_ATTEMPTS, e
+                    );
+                    last_error = e.to_string();
+                }
+            }
+            if attempt < STARTUP_SPAWN_ATTEMPTS {
+                tokio::time::sleep(STARTUP_RETRY_DELAY * attempt).await;
+            }
+        }
+
+        error!("Giving up on starting the sidecar: {}", last_error);
+        app.state::<SidecarState>()
+            .set_start_error(Some(last_error.clone()));
+        lifecycle::emit(&app, SidecarEvent::Stopped);
+        if crate::splash::show_error(&app, &last_error) && !missing {
+            return;
+        }
+        let hint = if missing {
+            format!("\n\n{}", tr(&app, Msg::MissingBinaryHint))
+        } else {
+            String::new()
+        };
+        let handle = app.clone();
+        app.dialog()
+            .message(format!(
+                "{}\n\n{}{}\n\n{}",
+                tr(&app, Msg::StartFailed),
+                last_error,
+                hint,
+                tr(&app, Msg::QuitPrompt)
+            ))
+            .title("NullGravity")
+            .kind(MessageDialogKind::Error)
+            .buttons(MessageDialogButtons::OkCancelCustom(
+                tr(&app, Msg::Quit).into(),
+                tr(&app, Msg::Continue).into(),
+            ))
+            .show(move |quit| {
+                if quit {
+                    handle.exit(1);
+                }
+            });
+    });
+}
+
+/// `prepare` 交给 `wire` 的上下文
+struct Launch {
+    info: LaunchInfo,
+    settings: Settings,
+    port: u16,
+}
+
+/// 在持有 child 锁时由控制器调用：检查授权、确认端口、构造启动命令
+///
+/// 首次启动、重启和 watchdog 重启都经过这里。
+fn prepare(app: &AppHandle, state: &SidecarState) -> CommandResult<(Command, OutputMode, Launch)> {
+    crate::activation::ensure_activated(app)?;
+    let port = ensure_port(app)?;
+
+    let settings = app.state::<SettingsState>().get();
+    let config = &settings.sidecar;
+    lifecycle::emit(app, SidecarEvent::Starting);
+
+    // 端口同时通过参数和环境变量传给 sidecar
+    let command = command_for(app, config)
+        .map_err(|e| {
+            state
+                .controller
+                .set_spawn_error(Some(SpawnFailureKind::of_command_error(
+                    &config.bin_name,
+                    &e,
+                )));
+            e
+        })?
+        .args(build_args(cfg!(debug_assertions)))
+        .args(["--port", &port.to_string()])
+        .env("NULLGRAVITY_PORT", port.to_string())
+        .env("RUST_LOG", crate::logging::sidecar_level());
+    // 自行 spawn 而不是走 shell 插件，才能把 sidecar 放进独立进程组 / Job Object
+    let command: Command = command.into();
+    let info = LaunchInfo::capture(&command, &config.bin_name, &app.state::<Redactor>());
+    let mode = settings.output_mode;
+    Ok((
+        command,
+        mode,
+        Launch {
+            info,
+            settings,
+            port,
+        },
+    ))
+}
+
+/// 新进程起来后：记录启动信息，挂上输出转发、就绪检测、watchdog 和心跳，返回 PID
+fn wire(app: &AppHandle, state: &SidecarState, started: Started<Events, Launch>) -> u32 {
+    let Started {
+        pid,
+        output: mut rx,
+        context: Launch {
+            mut info,
+            settings,
+            port,
+        },
+    } = started;
+    info.pid = pid;
+    *lock_recover(&state.launch, "Sidecar launch info") = Some(info);
+    apply_priority(pid, settings.sidecar_priority);
+    state.port_conflict.store(false, Ordering::SeqCst);
+    state.set_start_error(None);
+    state.pipe_broken.store(false, Ordering::SeqCst);
+    info!("Backend sidecar started, PID={}, PORT={}", pid, port);
+    crate::startup::mark(app, "spawn");
+    crate::tray::refresh(app, true);
+
+    let handle = app.clone();
+    let sentinel = match settings.readiness {
+        ReadinessMode::Sentinel(sentinel) => Some(sentinel),
+        ReadinessMode::PortProbe => {
+            probe_ready(app.clone(), pid, port);
+            None
+        }
+    };
+    let mode = settings.output_mode;
+    tauri::async_runtime::spawn(async move {
+        let mut exit = None;
+        // chunks 模式下按流还原出整行，控制协议和日志文件仍然按行处理
+        let mut stdout_lines = ChunkAssembler::default();
+        let mut stderr_lines = ChunkAssembler::default();
+        while let Some(event) = rx.recv().await {
+            match (event, mode) {
+                (ProcessEvent::Stdout(bytes), OutputMode::Lines) => {
+                    if let Some(line) = on_stdout_line(&handle, pid, &sentinel, decode_line(&bytes))
+                    {
+                        crate::output::publish(&handle, "stdout", log::Level::Info, line);
+                    }
+                }
+                (ProcessEvent::Stdout(bytes), OutputMode::Chunks) => {
+                    let (text, lines) = stdout_lines.push(&bytes);
+                    let mut output = lines.is_empty() || stdout_lines.has_pending();
+                    for line in lines {
+                        output |= on_stdout_line(&handle, pid, &sentinel, line).is_some();
+                    }
+                    // 整块都是 RPC 响应或 pong 时不当作输出转发
+                    if output {
+                        let text = handle.state::<Redactor>().redact(text);
+                        crate::output::publish_chunk(&handle, "stdout", log::Level::Info, text);
+                    }
+                }
+                (ProcessEvent::Stderr(bytes), OutputMode::Lines) => {
+                    let line = decode_line(&bytes);
+                    let (level, line) = on_stderr_line(&handle, pid, line);
+                    crate::output::publish(&handle, "stderr", level, line);
+                }
+                (ProcessEvent::Stderr(bytes), OutputMode::Chunks) => {
+                    let (text, lines) = stderr_lines.push(&bytes);
+                    let levels: Vec<log::Level> = lines
+                        .into_iter()
+                        .map(|line| on_stderr_line(&handle, pid, line).0)
+                        .collect();
+                    let level = levels.first().copied().unwrap_or(log::Level::Warn);
+                    let text = handle.state::<Redactor>().redact(text);
+                    crate::output::publish_chunk(&handle, "stderr", level, text);
+                }
+                (ProcessEvent::Terminated(status), _) => {
+                    warn!("Sidecar PID={} terminated: {:?}", pid, status);
+                    exit = Some(status);
+                    break;
+                }
+            }
+        }
+        supervise_exit(handle, pid, exit).await;
+    });
+    crate::heartbeat::start(app.clone(), pid);
+
+    pid
+}
+
+/// 处理 stdout 的一整行：RPC 响应和 pong 直接消化返回 None，其余写日志、检查端口冲突和就绪标记后返回脱敏的行
+fn on_stdout_line(
+    app: &AppHandle,
+    pid: u32,
+    sentinel: &Option<String>,
+    line: String,
+) -> Option<String> {
+    if crate::rpc::dispatch(app, &line) {
+        return None;
+    }
+    if line.trim() == crate::heartbeat::PONG {
+        app.state::<crate::heartbeat::HeartbeatState>().on_pong();
+        return None;
+    }
+    let line = app.state::<Redactor>().redact(line);
+    info!(target: SIDECAR_TARGET, "{}", line);
+    if is_port_conflict(&line) {
+        on_port_conflict(app, pid);
+    }
+    if sentinel.as_ref().is_some_and(|s| line.contains(s.as_str())) {
+        mark_ready(app);
+    }
+    Some(line)
+}
+
+/// 处理 stderr 的一整行，返回识别出的级别和脱敏的行
+fn on_stderr_line(app: &AppHandle, pid: u32, line: String) -> (log::Level, String) {
+    let line = app.state::<Redactor>().redact(line);
+    // Python 的 logging 默认写 stderr，按行首级别转发，认不出的才当作警告
+    let level = crate::logging::parse_severity(&line).unwrap_or(log::Level::Warn);
+    log::log!(target: SIDECAR_TARGET, level, "{}", line);
+    if is_port_conflict(&line) {
+        on_port_conflict(app, pid);
+    }
+    (level, line)
+}
+
+/// 随构建类型附加的参数，`debug` 取 `cfg!(debug_assertions)`
+fn build_args(debug: bool) -> &'static [&'static str] {
+    if debug {
+        DEV_ARGS
+    } else {
+        &[]
+    }
+}
+
+/// 按配置构造启动命令：通过 shell 插件解析 externalBin 路径，带上参数、环境变量和工作目录
+pub(crate) fn command_for(
+    app: &AppHandle,
+    config: &SidecarConfig,
+) -> CommandResult<tauri_plugin_shell::process::Command> {
+    ensure_bundled(app, &config.bin_name)?;
+    crate::binary::verify(&config.bin_name)?;
+    let mut command = app
+        .shell()
+        .sidecar(&config.bin_name)
+        .map_err(|e| {
+            CommandError::SpawnFailed(format!("sidecar '{}' not found: {}", config.bin_name, e))
+        })?
+        .args(&config.args)
+        .envs(config.env.clone());
+    if let Some(cwd) = &config.cwd {
+        command = command.current_dir(cwd);
+    }
+    if let Some(dir) = crate::paths::sidecar_data_dir(app) {
+        command = command.env(crate::paths::SIDECAR_DATA_DIR_ENV, dir);
+    }
+    Ok(command)
+}
+
+/// 只允许启动随应用打包的 externalBin，防止被篡改的配置或页面借 sidecar 拉起任意程序
+fn ensure_bundled(app: &AppHandle, bin_name: &str) -> CommandResult<()> {
+    let bundled = app.config().bundle.external_bin.clone().unwrap_or_default();
+    if is_bundled(bin_name, &bundled) {
+        Ok(())
+    } else {
+        warn!(
+            "Refusing to spawn '{}', it is not a bundled sidecar",
+            bin_name
+        );
+        Err(CommandError::InvalidArgument(format!(
+            "'{}' is not a bundled sidecar",
+            bin_name
+        )))
+    }
+}
+
+/// bin_name 必须是不含路径的纯文件名，且与某个 externalBin 条目的文件名完全一致
+fn is_bundled(bin_name: &str, external_bin: &[String]) -> bool {
+    let plain = !bin_name.is_empty()
+        && !bin_name.starts_with('.')
+        && bin_name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    plain
+        && external_bin.iter().any(|entry| {
+            std::path::Path::new(entry)
+                .file_name()
+                .is_some_and(|name| name == bin_name)
+        })
+}
+
+/// 前端通过 shell 插件能执行的只有打包的 sidecar，setup 中注册，代替写在 capabilities 里的配置
+///
+/// 其余程序一律被插件的 scope 拒绝，页面被攻破也拉不起任意进程。
+pub(crate) fn shell_capability() -> CapabilityBuilder {
+    CapabilityBuilder::new("sidecar-capability")
+        .window("main")
+        .permission_scoped(
+            "shell:allow-execute",
+            vec![serde_json::json!({
+                "name": format!("binaries/{}", SIDECAR_NAME),
+                "sidecar": true,
+            })],
+            Vec::<serde_json::Value>::new(),
+        )
+}
+
+/// 启动前确认端口仍然空闲，探测与绑定之间被抢占时换一个
+fn ensure_port(app: &AppHandle) -> CommandResult<u16> {
+    let managed = app.state::<ManagedPort>();
+    let mut guard = lock_recover(&managed.0, "Managed port");
+    let previous = *guard;
+    let range = app.state::<SettingsState>().get().port_range;
+    // 启动时分配的端口可能不在配置的端口段内
+    if let (Some(port), Some((lo, hi))) = (*guard, range) {
+        if !(lo..=hi).contains(&port) {
+            *guard = None;
+        }
+    }
+
+    for _ in 0..PORT_RETRY_LIMIT {
+        let candidate = match *guard {
+            Some(port) => port,
+            None => match range {
+                Some((lo, hi)) => port::pick_free_port_in_range(lo, hi),
+                None => port::pick_free_port(),
+            }
+            .map_err(|e| CommandError::SpawnFailed(format!("no usable port: {}", e)))?,
+        };
+        if port::is_free(candidate) {
+            *guard = Some(candidate);
+            if previous != Some(candidate) {
+                publish_port(app, candidate);
+            }
+            return Ok(candidate);
+        }
+        warn!("Port {} is already in use, picking another", candidate);
+        *guard = None;
+    }
+    Err(CommandError::SpawnFailed(format!(
+        "no free port after {} attempts",
+        PORT_RETRY_LIMIT
+    )))
+}
+
+/// 端口变化后同步给已加载的页面
+fn publish_port(app: &AppHandle, port: u16) {
+    info!("Backend port switched to {}", port);
+    lifecycle::emit(app, SidecarEvent::PortChanged { port });
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.eval(format!("window.__BACKEND_PORT__ = {};", port));
+    }
+}
+
+/// 手动重启：全程持有 child 锁，并发调用只会排队执行
+pub fn restart(app: &AppHandle) -> CommandResult<u32> {
+    let state = app.state::<SidecarState>();
+    if state.is_shutting_down() {
+        return Err(CommandError::ShuttingDown);
+    }
+    state.ensure_owned()?;
+
+    let started = state.controller.restart(
+        |child| {
+            let outcome = stop_child(app, child);
+            info!("Sidecar stopped for restart: {:?}", outcome);
+            lifecycle::emit(app, SidecarEvent::Stopped);
+            app.state::<crate::rpc::RpcState>().fail_all();
+        },
+        || prepare(app, &state),
+    )?;
+    Ok(wire(app, &state, started))
+}
+
+/// 用户发起的重启（托盘、启动页重试、前端按钮）：和 retry_start_sidecar 一样先闭合熔断器
+pub fn restart_by_user(app: &AppHandle) -> CommandResult<u32> {
+    let state = app.state::<SidecarState>();
+    if state.is_shutting_down() {
+        return Err(CommandError::ShuttingDown);
+    }
+    state.ensure_owned()?;
+    state.close_breaker();
+    restart(app)
+}
+
+/// 自动发起的重启（可执行文件更新、日志级别同步）：熔断打开时拒绝，不绕过熔断拉起后端
+pub fn restart_unless_tripped(app: &AppHandle) -> CommandResult<u32> {
+    if app.state::<SidecarState>().breaker.is_open() {
+        return Err(CommandError::CircuitOpen);
+    }
+    restart(app)
+}
+
+#[tauri::command]
+pub async fn restart_sidecar(app: AppHandle) -> CommandResult<()> {
+    // 优雅关闭会阻塞等待，放到阻塞线程池里跑
+    blocking(move || restart_by_user(&app)).await.map(|_| ())
+}
+
+fn mark_ready(app: &AppHandle) {
+    let state = app.state::<SidecarState>();
+    if state.controller.mark_ready() {
+        info!("Sidecar is ready");
+        crate::startup::mark(app, "ready");
+        state.port_retries.store(0, Ordering::SeqCst);
+        state.crash_dialog_shown.store(false, Ordering::SeqCst);
+        flush_outbound(&state);
+        lifecycle::emit(app, SidecarEvent::Ready);
+        // 外部后端没有 stdin RPC，无从握手
+        if !state.is_external() {
+            crate::version::handshake(app);
+        }
+        crate::deeplink::flush(app);
+        crate::idle::on_ready(app);
+        crate::window::reveal_once(app);
+    }
+}
+
+/// 调不动优先级（比如没有权限提高）只记录警告，不影响启动
+fn apply_priority(pid: u32, priority: process::Priority) {
+    if priority == process::Priority::Normal {
+        return;
+    }
+    match process::set_priority(pid, priority) {
+        Ok(()) => info!("Sidecar PID={} priority set to {:?}", pid, priority),
+        Err(e) => warn!(
+            "Failed to set sidecar PID={} priority to {:?}: {}",
+            pid, priority, e
+        ),
+    }
+}
+
+/// 每 READY_PROBE_INTERVAL 尝试连接一次端口，连上即就绪；进程被替换或超过 READY_TIMEOUT 后放弃
+fn probe_ready(app: AppHandle, pid: u32, port: u16) {
+    tauri::async_runtime::spawn(async move {
+        let deadline = Instant::now() + READY_TIMEOUT;
+        let addr = std::net::SocketAddr::from(([127, 0, 0, 1], port));
+        while Instant::now() < deadline {
+            if app.state::<SidecarState>().is_shutting_down() || current_pid(&app) != Some(pid) {
+                return;
+            }
+            let connected = blocking(move || {
+                Ok(std::net::TcpStream::connect_timeout(&addr, READY_PROBE_INTERVAL).is_ok())
+            })
+            .await
+            .unwrap_or(false);
+            if connected {
+                mark_ready(&app);
+                return;
+            }
+            tokio::time::sleep(READY_PROBE_INTERVAL).await;
+        }
+        warn!(
+            "Sidecar PID={} did not accept connections on port {} within {:?}",
+            pid, port, READY_TIMEOUT
+        );
+    });
+}
+
+/// 连接外部启动的后端，代替 spawn_with_retry
+///
+/// 不拉起进程，也没有 watchdog 和重启：端口能连上就标记就绪，之后每 EXTERNAL_PROBE_INTERVAL
+/// 探测一次，断开时发 Stopped，重新连上时再发 Ready。
+pub fn attach_external(app: AppHandle, port: u16) {
+    info!("Using external sidecar on port {}, not spawning one", port);
+    app.state::<SidecarState>()
+        .external
+        .store(true, Ordering::SeqCst);
+    tauri::async_runtime::spawn(async move {
+        let addr = std::net::SocketAddr::from(([127, 0, 0, 1], port));
+        loop {
+            let state = app.state::<SidecarState>();
+            if state.is_shutting_down() {
+                return;
+            }
+            let connected = blocking(move || {
+                Ok(std::net::TcpStream::connect_timeout(&addr, READY_PROBE_INTERVAL).is_ok())
+            })
+            .await
+            .unwrap_or(false);
+            if connected {
+                mark_ready(&app);
+            } else if state.controller.mark_not_ready() {
+                warn!(
+                    "External sidecar on port {} stopped accepting connections",
+                    port
+                );
+                lifecycle::emit(&app, SidecarEvent::Stopped);
+            }
+            let interval = if state.is_ready() {
+                EXTERNAL_PROBE_INTERVAL
+            } else {
+                READY_PROBE_INTERVAL
+            };
+            tokio::time::sleep(interval).await;
+        }
+    });
+}
+
+/// 后端约定打印 PORT_IN_USE；同时兼容 uvicorn 直接抛出的绑定错误
+fn is_port_conflict(line: &str) -> bool {
+    if line.contains("PORT_IN_USE") {
+        return true;
+    }
+    let lower = line.to_ascii_lowercase();
+    lower.contains("address already in use")
+        || lower.contains("eaddrinuse")
+        || lower.contains("only one usage of each socket address")
+}
+
+/// 记下冲突并清空端口，结束进程后由 supervise_exit 换端口重启
+fn on_port_conflict(app: &AppHandle, pid: u32) {
+    let state = app.state::<SidecarState>();
+    if state.port_conflict.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let port = lock_recover(&app.state::<ManagedPort>().0, "Managed port").take();
+    warn!("Sidecar PID={} reports port {:?} is in use", pid, port);
+
+    let handle = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let state = handle.state::<SidecarState>();
+        let guard = state.lock_child();
+        if let Some(child) = guard.as_ref().filter(|c| c.pid() == pid) {
+            child.kill();
+        }
+    });
+}
+
+pub(crate) fn decode_line(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes)
+        .trim_end_matches(['\r', '\n'])
+        .to_string()
+}
+
+/// 非主动退出时推给前端的崩溃信息
+#[derive(Clone, Serialize)]
+pub struct SidecarCrash {
+    pub code: Option<i32>,
+    pub signal: Option<i32>,
+}
+
+/// 退出码非 0 或被信号杀死都算崩溃
+fn is_crash(exit: &Option<ExitStatus>) -> bool {
+    match exit {
+        Some(status) => status.code != Some(0) || status.signal.is_some(),
+        None => true,
+    }
+}
+
+fn report_crash(app: &AppHandle, exit: Option<ExitStatus>) {
+    let crash = SidecarCrash {
+        code: exit.as_ref().and_then(|s| s.code),
+        signal: exit.as_ref().and_then(|s| s.signal),
+    };
+    let _ = app.emit("sidecar-crashed", crash.clone());
+
+    // 熔断打开后由 splash 报告，不再叠加对话框
+    let state = app.state::<SidecarState>();
+    if state.breaker.is_open() || state.crash_dialog_shown.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let detail = match (crash.code, crash.signal) {
+        (_, Some(signal)) => format!("killed by signal {}", signal),
+        (Some(code), None) => format!("exit code {}", code),
+        (None, None) => "unknown exit status".to_string(),
+    };
+    app.dialog()
+        .message(i18n::format(
+            tr(app, Msg::BackendCrashed),
+            &[("detail", &detail)],
+        ))
+        .title("NullGravity")
+        .kind(MessageDialogKind::Error)
+        .show(|_| {});
+}
+
+/// 进程退出后的处理：清理旧句柄，非主动关闭时按退避策略重启
+async fn supervise_exit(app: AppHandle, pid: u32, exit: Option<ExitStatus>) {
+    let state = app.state::<SidecarState>();
+
+    // 只清理属于本进程的句柄，避免误删已经换上的新进程
+    match state.controller.release(pid) {
+        Release::Released => {
+            app.state::<crate::rpc::RpcState>().fail_all();
+            crate::tray::refresh(&app, false);
+        }
+        // 已经有新进程接替（手动重启），无需 watchdog 介入
+        Release::Replaced => return,
+        Release::Vacant => {}
+    }
+
+    app.state::<crate::diagnostics::DiagnosticsState>()
+        .record_exit(exit);
+    let crashed = match state.classify_exit(&exit) {
+        ExitAction::Ignore => return,
+        ExitAction::NewPort => {
+            respawn_on_new_port(&app);
+            return;
+        }
+        ExitAction::Respawn { crashed } => crashed,
+    };
+
+    if crashed {
+        lifecycle::emit(
+            &app,
+            SidecarEvent::Crashed {
+                code: exit.as_ref().and_then(|s| s.code),
+            },
+        );
+        report_crash(&app, exit);
+        if state.breaker.is_open() {
+            return;
+        }
+        let settings = app.state::<SettingsState>().get();
+        let limit = settings.circuit_breaker_crashes;
+        let window = settings.circuit_breaker_window();
+        if state.breaker.record_crash(Instant::now(), limit, window) {
+            error!(
+                "Sidecar crashed more than {} times within {:?}, stopping auto-respawn",
+                limit, window
+            );
+            lifecycle::emit(
+                &app,
+                SidecarEvent::CircuitOpen {
+                    crashes: limit,
+                    window_secs: window.as_secs(),
+                },
+            );
+            crate::splash::show_error(&app, tr(&app, Msg::KeepsCrashing));
+            return;
+        }
+    }
+
+    state.controller.reset_respawns_if_stable();
+
+    loop {
+        let Some((attempt, delay)) = state.controller.next_respawn() else {
+            error!(
+                "Sidecar failed {} times in a row, giving up on respawn",
+                MAX_RESPAWN_ATTEMPTS
+            );
+            lifecycle::emit(&app, SidecarEvent::Stopped);
+            crate::splash::show_error(&app, tr(&app, Msg::KeepsCrashingOnStartup));
+            return;
+        };
+        warn!(
+            "Respawning sidecar in {:?} (attempt {}/{})",
+            delay, attempt, MAX_RESPAWN_ATTEMPTS
+        );
+        lifecycle::emit(&app, SidecarEvent::Restarting { attempt });
+        tokio::time::sleep(delay).await;
+
+        if state.is_shutting_down() || state.breaker.is_open() || state.lock_child().is_some() {
+            return;
+        }
+        match spawn(&app) {
+            Ok(new_pid) => {
+                info!("Sidecar respawned, PID={}", new_pid);
+                return;
+            }
+            Err(e) => warn!("Respawn attempt {} failed: {}", attempt, e),
+        }
+    }
+}
+
+/// 端口冲突退出：ManagedPort 已清空，立即在新端口上重启，连续冲突太多次后放弃
+fn respawn_on_new_port(app: &AppHandle) {
+    let state = app.state::<SidecarState>();
+    let attempt = state.port_retries.fetch_add(1, Ordering::SeqCst) + 1;
+    if attempt > PORT_RETRY_LIMIT {
+        error!(
+            "Sidecar hit a port conflict {} times in a row, giving up",
+            PORT_RETRY_LIMIT
+        );
+        lifecycle::emit(app, SidecarEvent::Stopped);
+        crate::splash::show_error(app, tr(app, Msg::NoUsablePort));
+        return;
+    }
+    // spawn 时会挑一个新端口
+    match spawn(app) {
+        Ok(new_pid) => info!("Sidecar respawned on a new port, PID={}", new_pid),
+        Err(e) => error!("Respawn after port conflict failed: {}", e),
+    }
+}
+
+/// 先请求 sidecar 自行退出，超过 grace_ms 仍存活再强杀
+///
+/// 会阻塞调用线程直到进程退出或超时。
+pub fn shutdown_sidecar(app: &AppHandle) -> ShutdownOutcome {
+    let state = app.state::<SidecarState>();
+    state.begin_shutdown();
+    crate::services::stop_all(app);
+
+    let Some(outcome) = state.controller.stop(|child| stop_child(app, child)) else {
+        return ShutdownOutcome::AlreadyDead;
+    };
+    lifecycle::emit(app, SidecarEvent::Stopped);
+    outcome
+}
+
+/// 不等锁、不等退出，直接结束当前 sidecar；只在 panic 路径使用
+pub fn kill_now(app: &AppHandle) {
+    let state = app.state::<SidecarState>();
+    state.begin_shutdown();
+    crate::services::kill_all_now(app);
+    if let Some(child) = state.controller.try_lock_child().and_then(|mut g| g.take()) {
+        child.kill();
+    }
+}
+
+/// 强杀后依然存活时推给前端
+#[derive(Clone, Serialize)]
+struct StuckProcess {
+    pid: u32,
+}
+
+/// 两段式关闭：请求退出并等待 grace_ms，强杀后再等 hard_ms，仍然存活则报告卡死而不是无限阻塞
+pub(crate) fn stop_child(app: &AppHandle, child: SidecarChild) -> ShutdownOutcome {
+    let grace = app.state::<SettingsState>().get().grace();
+    stop_child_with(app, child, grace)
+}
+
+/// 同 `stop_child`，使用指定的 grace；附加服务可以各自配置
+///
+/// 卡死的进程交给 `reap_stuck` 在后台继续清理，调用方不用等。
+pub(crate) fn stop_child_with(
+    app: &AppHandle,
+    child: SidecarChild,
+    grace: Duration,
+) -> ShutdownOutcome {
+    let timeouts = StopTimeouts {
+        grace,
+        hard: app.state::<SettingsState>().get().hard_timeout(),
+    };
+    let outcome = child.stop(timeouts);
+    if outcome == ShutdownOutcome::Stuck {
+        let _ = app.emit("stuck-process", StuckProcess { pid: child.pid() });
+        reap_stuck(app, child);
+    }
+    outcome
+}
+
+/// 强杀后仍未退出的进程：后台线程每 STUCK_KILL_INTERVAL 再杀一次，最多 STUCK_KILL_RETRIES 次
+///
+/// 用独立线程而不是 async 任务，窗口关闭、事件循环结束后也能继续；退出前由 `join_reapers` 等它们结束。
+fn reap_stuck(app: &AppHandle, child: SidecarChild) {
+    let pid = child.pid();
+    lifecycle::emit(app, SidecarEvent::ShutdownStuck { pid });
+    let reaper = std::thread::spawn(move || {
+        for attempt in 1..=STUCK_KILL_RETRIES {
+            std::thread::sleep(STUCK_KILL_INTERVAL);
+            if !child.is_alive() {
+                info!("Stuck sidecar PID={} is finally gone", pid);
+                return;
+            }
+            warn!(
+                "Killing stuck sidecar PID={} again ({}/{})",
+                pid, attempt, STUCK_KILL_RETRIES
+            );
+            child.kill();
+        }
+        std::thread::sleep(STUCK_KILL_INTERVAL);
+        if child.is_alive() {
+            log::error!(
+                "Giving up on stuck sidecar PID={} after {} kill attempts, it may be leaked",
+                pid,
+                STUCK_KILL_RETRIES
+            );
+        } else {
+            info!("Stuck sidecar PID={} is finally gone", pid);
+        }
+    });
+    lock_recover(&app.state::<SidecarState>().reapers, "Sidecar reapers").push(reaper);
+}
+
+/// 退出前等所有卡死进程的清理线程结束，最长约 STUCK_KILL_RETRIES × STUCK_KILL_INTERVAL
+pub fn join_reapers(app: &AppHandle) {
+    let reapers = std::mem::take(&mut *lock_recover(
+        &app.state::<SidecarState>().reapers,
+        "Sidecar reapers",
+    ));
+    if reapers.is_empty() {
+        return;
+    }
+    info!(
+        "Waiting for {} stuck process(es) to be cleaned up",
+        reapers.len()
+    );
+    for reaper in reapers {
+        let _ = reaper.join();
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::process::Command;
+
+    use super::*;
+
+    const TIMEOUTS: StopTimeouts = StopTimeouts {
+        grace: Duration::from_secs(2),
+        hard: Duration::from_secs(2),
+    };
+
+    /// 起一个只读 stdin 的假 sidecar 再停掉它，`intentional` 模拟 restart 在停止前置位
+    fn stop_fake_sidecar(state: &SidecarState, intentional: bool) -> Option<ExitStatus> {
+        let mut command = Command::new("sh");
+        command.args(["-c", "while read line; do :; done"]);
+        let (mut rx, child) = process::spawn(command).unwrap();
+        if intentional {
+            state.controller.mark_intentional_stop();
+        }
+        child.stop(TIMEOUTS);
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            tokio::time::timeout(Duration::from_secs(5), async {
+                while let Some(event) = rx.recv().await {
+                    if let ProcessEvent::Terminated(status) = event {
+                        return Some(status);
+                    }
+                }
+                None
+            })
+            .await
+            .expect("sidecar did not report termination")
+        })
+    }
+
+    #[test]
+    fn restart_does_not_report_a_crash() {
+        let state = SidecarState::default();
+        let exit = stop_fake_sidecar(&state, true);
+        // 单看退出状态（被 SIGTERM 结束）和崩溃没有区别
+        assert!(is_crash(&exit));
+        assert_eq!(state.classify_exit(&exit), ExitAction::Ignore);
+        // 标记只对这一次退出生效
+        assert_eq!(
+            state.classify_exit(&exit),
+            ExitAction::Respawn { crashed: true }
+        );
+    }
+
+    #[test]
+    fn unexpected_exit_is_reported_as_a_crash() {
+        let state = SidecarState::default();
+        let exit = stop_fake_sidecar(&state, false);
+        assert_eq!(
+            state.classify_exit(&exit),
+            ExitAction::Respawn { crashed: true }
+        );
+    }
+
+    #[test]
+    fn messages_before_ready_are_queued_and_can_be_withdrawn() {
+        let state = SidecarState::default();
+        assert_eq!(
+            send_or_queue(&state, "first".into()).unwrap(),
+            Delivery::Queued
+        );
+        send_or_queue(&state, "second".into()).unwrap();
+        assert!(unqueue(&state, "first"));
+        assert!(!unqueue(&state, "first"));
+        let queue = lock_recover(&state.outbound, "Sidecar outbound queue");
+        assert_eq!(queue.iter().collect::<Vec<_>>(), ["second"]);
+    }
+
+    #[test]
+    fn sends_after_ready_retry_the_leftover_queue() {
+        let state = SidecarState::default();
+        send_or_queue(&state, "stale".into()).unwrap();
+        state.controller.mark_ready();
+        // 补写失败时直接报错，不再把新消息排到一个不会被 flush 的队列后面
+        assert!(matches!(
+            send_or_queue(&state, "next".into()),
+            Err(CommandError::SidecarNotRunning)
+        ));
+        let queue = lock_recover(&state.outbound, "Sidecar outbound queue");
+        assert_eq!(queue.iter().collect::<Vec<_>>(), ["stale"]);
+    }
+
+    #[test]
+    fn only_bundled_sidecars_may_be_spawned() {
+        let bundled = vec!["binaries/nullgravity-core".to_string()];
+        assert!(is_bundled(SIDECAR_NAME, &bundled));
+        for name in [
+            "sh",
+            "/bin/sh",
+            "../../bin/sh",
+            "binaries/nullgravity-core",
+            "nullgravity-core; rm -rf ~",
+            "..",
+            "",
+        ] {
+            assert!(!is_bundled(name, &bundled), "{:?} was allowed", name);
+        }
+    }
+
+    #[test]
+    fn shell_scope_rejects_other_programs() {
+        use tauri::ipc::{CallbackFn, InvokeBody};
+        use tauri::webview::InvokeRequest;
+
+        let app = tauri::test::mock_builder()
+            .plugin(tauri_plugin_shell::init())
+            .build(tauri::generate_context!())
+            .unwrap();
+        app.add_capability(shell_capability()).unwrap();
+        let webview = tauri::WebviewWindowBuilder::new(&app, "main", Default::default())
+            .build()
+            .unwrap();
+
+        let execute = |program: &str| {
+            tauri::test::get_ipc_response(
+                &webview,
+                InvokeRequest {
+                    cmd: "plugin:shell|execute".into(),
+                    callback: CallbackFn(0),
+                    error: CallbackFn(1),
+                    url: "tauri://localhost".parse().unwrap(),
+                    body: InvokeBody::Json(serde_json::json!({
+                        "program": program,
+                        "args": ["-c", "true"],
+                        "options": { "sidecar": false },
+                    })),
+                    headers: Default::default(),
+                    invoke_key: tauri::test::INVOKE_KEY.to_string(),
+                },
+            )
+        };
+        for program in ["sh", "/bin/sh", "nullgravity-core"] {
+            let error = execute(program)
+                .map(|_| ())
+                .expect_err("a program outside the shell scope was executed");
+            assert!(
+                error.to_string().contains("not allowed"),
+                "{} was rejected for the wrong reason: {}",
+                program,
+                error
+            );
+        }
+    }
+
+    #[test]
+    fn dev_args_are_only_passed_in_debug_builds() {
+        assert_eq!(build_args(true), ["--dev", "--verbose"]);
+        assert!(build_args(false).is_empty());
+    }
+
+    #[test]
+    fn io_errors_map_to_spawn_failure_kinds() {
+        use std::io::{Error, ErrorKind};
+
+        assert_eq!(
+            SpawnFailureKind::classify(&Error::from(ErrorKind::NotFound)),
+            SpawnFailureKind::BinaryNotFound
+        );
+        assert_eq!(
+            SpawnFailureKind::classify(&Error::from(ErrorKind::PermissionDenied)),
+            SpawnFailureKind::PermissionDenied
+        );
+        let other = Error::new(ErrorKind::InvalidInput, "exec format error");
+        assert_eq!(
+            SpawnFailureKind::classify(&other),
+            SpawnFailureKind::Io("exec format error".to_string())
+        );
+    }
+
+    #[test]
+    fn real_spawn_errors_are_classified() {
+        let missing = Command::new("/nonexistent/nullgravity-core")
+            .spawn()
+            .unwrap_err();
+        assert_eq!(
+            SpawnFailureKind::classify(&missing),
+            SpawnFailureKind::BinaryNotFound
+        );
+
+        // 没有执行权限的文件
+        let path = std::env::temp_dir().join(format!("nullgravity-noexec-{}", std::process::id()));
+        std::fs::write(&path, "#!/bin/sh\n").unwrap();
+        let denied = Command::new(&path).spawn().unwrap_err();
+        let probed = crate::binary::probe(&path).unwrap_err();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            SpawnFailureKind::classify(&denied),
+            SpawnFailureKind::PermissionDenied
+        );
+        assert_eq!(
+            SpawnFailureKind::classify(&probed),
+            SpawnFailureKind::PermissionDenied
+        );
+    }
+
+    #[test]
+    fn shutdown_suppresses_the_watchdog() {
+        let state = SidecarState::default();
+        state.begin_shutdown();
+        let exit = stop_fake_sidecar(&state, false);
+        assert_eq!(state.classify_exit(&exit), ExitAction::Ignore);
+    }
+}
@@ -0,0 +1,83 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use tauri::{AppHandle, Manager, Theme, WebviewUrl, WebviewWindowBuilder};
+
+use crate::error::CommandResult;
+use crate::i18n::{tr, Msg};
+
+pub const SPLASH_LABEL: &str = "splash";
+
+/// 启动画面状态
+#[derive(Default)]
+pub struct SplashState {
+    /// 启动失败、正在等待用户选择重试或退出
+    failed: AtomicBool,
+}
+
+/// sidecar 启动期间显示的小窗口，就绪或超时后由 `window::reveal_once` 关闭
+pub fn open(app: &AppHandle, theme: Option<Theme>) -> tauri::Result<()> {
+    // splash.html 里只有英文兜底，界面文案按当前语言注入
+    let text = serde_json::json!({
+        "starting": tr(app, Msg::SplashStarting),
+        "failed": tr(app, Msg::SplashFailed),
+        "retry": tr(app, Msg::Retry),
+        "quit": tr(app, Msg::Quit),
+    });
+    let script = format!("window.__SPLASH_TEXT__ = {};", text);
+    WebviewWindowBuilder::new(app, SPLASH_LABEL, WebviewUrl::App("splash.html".into()))
+        .title("NullGravity")
+        .theme(theme)
+        .initialization_script(&script)
+        .inner_size(360.0, 240.0)
+        .resizable(false)
+        .decorations(false)
+        .center()
+        .build()?;
+    Ok(())
+}
+
+pub fn close(app: &AppHandle) {
+    if let Some(splash) = app.get_webview_window(SPLASH_LABEL) {
+        let _ = splash.destroy();
+    }
+}
+
+/// 启动画面进入错误状态，没有启动画面时返回 false
+pub fn show_error(app: &AppHandle, message: &str) -> bool {
+    let Some(splash) = app.get_webview_window(SPLASH_LABEL) else {
+        return false;
+    };
+    app.state::<SplashState>()
+        .failed
+        .store(true, Ordering::SeqCst);
+    let message = serde_json::to_string(message).unwrap_or_default();
+    splash
+        .eval(format!(
+            "window.__SPLASH__ && window.__SPLASH__.fail({});",
+            message
+        ))
+        .is_ok()
+}
+
+pub fn has_failed(app: &AppHandle) -> bool {
+    app.state::<SplashState>().failed.load(Ordering::SeqCst)
+}
+
+#[tauri::command]
+pub async fn splash_retry(app: AppHandle) -> CommandResult<()> {
+    app.state::<SplashState>()
+        .failed
+        .store(false, Ordering::SeqCst);
+    let handle = app.clone();
+    let result = crate::task::blocking(move || crate::sidecar::restart_by_user(&handle)).await;
+    if let Err(e) = &result {
+        log::warn!("Sidecar retry from splash failed: {}", e);
+        show_error(&app, &e.to_string());
+    }
+    result.map(|_| ())
+}
+
+#[tauri::command]
+pub fn splash_quit(app: AppHandle) {
+    crate::shutdown::quit(&app, "splash quit");
+}
@@ -0,0 +1,68 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+use crate::sidecar::lock_recover;
+
+/// 冷启动各阶段距进程启动（进入 `run()`）的耗时，每个阶段只记第一次
+pub struct StartupTimings {
+    started: Instant,
+    /// 无窗口运行时以 ready 作为启动结束
+    headless: bool,
+    milestones: Mutex<Vec<(&'static str, Duration)>>,
+}
+
+#[derive(Serialize)]
+pub struct StartupMilestone {
+    pub name: &'static str,
+    pub ms: u64,
+}
+
+impl StartupTimings {
+    pub fn new(started: Instant, headless: bool) -> Self {
+        Self {
+            started,
+            headless,
+            milestones: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn milestones(&self) -> Vec<StartupMilestone> {
+        lock_recover(&self.milestones, "Startup milestones")
+            .iter()
+            .map(|(name, elapsed)| StartupMilestone {
+                name,
+                ms: elapsed.as_millis() as u64,
+            })
+            .collect()
+    }
+
+    fn summary(&self) -> String {
+        let parts: Vec<String> = self
+            .milestones()
+            .iter()
+            .map(|m| format!("{}={}ms", m.name, m.ms))
+            .collect();
+        format!("startup: {}", parts.join(" "))
+    }
+}
+
+/// 记录一个阶段；主窗口显示（无窗口时为 ready）即启动完成，输出汇总
+///
+/// 阶段名：plugins（插件初始化完毕、进入 setup）、spawn、ready、window。
+pub fn mark(app: &AppHandle, name: &'static str) {
+    let timings = app.state::<StartupTimings>();
+    {
+        let mut milestones = lock_recover(&timings.milestones, "Startup milestones");
+        if milestones.iter().any(|(n, _)| *n == name) {
+            return;
+        }
+        milestones.push((name, timings.started.elapsed()));
+    }
+    let last = if timings.headless { "ready" } else { "window" };
+    if name == last {
+        log::info!("{}", timings.summary());
+    }
+}
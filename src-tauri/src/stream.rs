@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tokio::sync::Notify;
+
+use crate::sidecar::lock_recover;
+
+/// 每个分片的大小
+const STREAM_CHUNK_BYTES: usize = 64 * 1024;
+/// 已发出但前端尚未确认的数据上限，超过后暂停发送
+const STREAM_MAX_IN_FLIGHT: usize = 4 * 1024 * 1024;
+/// 前端迟迟不确认时放弃该流
+const STREAM_ACK_TIMEOUT: Duration = Duration::from_secs(30);
+/// 大结果的后端调用比普通 RPC 允许更久
+const STREAM_CALL_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// 要以流式返回结果的后端调用
+#[derive(Deserialize)]
+pub struct StreamRequest {
+    pub method: String,
+    #[serde(default)]
+    pub params: Value,
+}
+
+/// 推给前端的一个分片；`done` 为 true 的最后一条不带数据，出错时带 `error`
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StreamChunk {
+    channel_id: String,
+    seq: u64,
+    data: String,
+    done: bool,
+    error: Option<String>,
+}
+
+/// 单个流的确认进度
+#[derive(Default)]
+struct Flow {
+    /// 前端已确认收到的分片数
+    acked: AtomicU64,
+    notify: Notify,
+}
+
+#[derive(Default)]
+pub struct StreamState {
+    next_id: AtomicU64,
+    flows: Mutex<HashMap<String, Arc<Flow>>>,
+}
+
+/// 发起后端调用并把结果分片推送到 "sidecar-stream" 事件，立即返回 channel id
+#[tauri::command]
+pub fn start_stream(app: AppHandle, state: State<StreamState>, request: StreamRequest) -> String {
+    let channel_id = format!(
+        "stream-{}",
+        state.next_id.fetch_add(1, Ordering::Relaxed) + 1
+    );
+    let flow = Arc::new(Flow::default());
+    lock_recover(&state.flows, "Stream flows").insert(channel_id.clone(), flow.clone());
+
+    let id = channel_id.clone();
+    tauri::async_runtime::spawn(async move {
+        let mut seq = 0;
+        let error = run(&app, &id, &flow, request, &mut seq).await.err();
+        if let Some(e) = &error {
+            log::warn!("Stream {} failed: {}", id, e);
+        }
+        let _ = app.emit(
+            "sidecar-stream",
+            chunk(&id, seq, String::new(), true, error),
+        );
+        lock_recover(&app.state::<StreamState>().flows, "Stream flows").remove(&id);
+    });
+    channel_id
+}
+
+/// 前端处理完分片后确认，`seq` 为已处理的最后一个分片序号
+#[tauri::command]
+pub fn stream_ack(state: State<StreamState>, channel_id: String, seq: u64) {
+    if let Some(flow) = lock_recover(&state.flows, "Stream flows").get(&channel_id) {
+        flow.acked
+            .fetch_max(seq.saturating_add(1), Ordering::SeqCst);
+        flow.notify.notify_one();
+    }
+}
+
+/// 逐片发送结果，`seq` 随之推进，结束时即为已发送的分片数
+async fn run(
+    app: &AppHandle,
+    id: &str,
+    flow: &Flow,
+    request: StreamRequest,
+    seq: &mut u64,
+) -> Result<(), String> {
+    let payload = crate::rpc::call(app, &request.method, request.params, STREAM_CALL_TIMEOUT)
+        .await
+        .map_err(|e| e.to_string())?;
+    let text = match payload {
+        Value::String(text) => text,
+        other => other.to_string(),
+    };
+
+    let window = (STREAM_MAX_IN_FLIGHT / STREAM_CHUNK_BYTES) as u64;
+    let mut rest = text.as_str();
+    while !rest.is_empty() {
+        // notify_one 在没有等待者时会留下许可，先检查再等待不会丢确认
+        while seq.saturating_sub(flow.acked.load(Ordering::SeqCst)) >= window {
+            if tokio::time::timeout(STREAM_ACK_TIMEOUT, flow.notify.notified())
+                .await
+                .is_err()
+            {
+                return Err("frontend stopped acknowledging chunks".to_string());
+            }
+        }
+
+        let (head, tail) = rest.split_at(char_boundary(rest, STREAM_CHUNK_BYTES));
+        app.emit(
+            "sidecar-stream",
+            chunk(id, *seq, head.to_string(), false, None),
+        )
+        .map_err(|e| e.to_string())?;
+        *seq += 1;
+        rest = tail;
+    }
+    Ok(())
+}
+
+fn chunk(id: &str, seq: u64, data: String, done: bool, error: Option<String>) -> StreamChunk {
+    StreamChunk {
+        channel_id: id.to_string(),
+        seq,
+        data,
+        done,
+        error,
+    }
+}
+
+/// 不超过 `max` 字节的最长前缀长度，且不切断 UTF-8 字符
+fn char_boundary(s: &str, max: usize) -> usize {
+    if s.len() <= max {
+        return s.len();
+    }
+    let mut end = max;
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    end
+}
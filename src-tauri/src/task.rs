@@ -0,0 +1,17 @@
+//! command 的线程约定
+//!
+//! 同步 command 直接在主线程上执行，会卡住 UI。凡是可能等锁、等子进程或做磁盘 I/O 的
+//! command 一律写成 async，阻塞部分通过 `blocking` 交给阻塞线程池；只读内存状态的
+//! 轻量 command 可以保持同步。
+use crate::error::{CommandError, CommandResult};
+
+/// 在阻塞线程池中执行 `f` 并等待结果
+pub async fn blocking<T, F>(f: F) -> CommandResult<T>
+where
+    F: FnOnce() -> CommandResult<T> + Send + 'static,
+    T: Send + 'static,
+{
+    tauri::async_runtime::spawn_blocking(f)
+        .await
+        .map_err(|e| CommandError::Io(e.to_string()))?
+}
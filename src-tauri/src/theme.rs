@@ -0,0 +1,80 @@
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, State, Theme, Window, WindowEvent};
+
+use crate::config::SettingsState;
+use crate::error::CommandResult;
+use crate::task::blocking;
+
+/// 窗口主题，保存在 settings.toml 的 `theme`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ThemePreference {
+    Dark,
+    Light,
+    /// 跟随系统，系统切换时窗口一起切换
+    #[default]
+    System,
+}
+
+impl ThemePreference {
+    /// 交给窗口 API 的主题，`None` 表示跟随系统
+    pub fn window_theme(self) -> Option<Theme> {
+        match self {
+            Self::Dark => Some(Theme::Dark),
+            Self::Light => Some(Theme::Light),
+            Self::System => None,
+        }
+    }
+}
+
+#[derive(Clone, Serialize)]
+struct ThemeChanged {
+    theme: &'static str,
+}
+
+/// 应用到所有已打开的窗口
+pub fn apply(app: &AppHandle, theme: ThemePreference) {
+    for window in app.webview_windows().values() {
+        if let Err(e) = window.set_theme(theme.window_theme()) {
+            log::warn!("Failed to set theme of window '{}': {}", window.label(), e);
+        }
+    }
+}
+
+/// 切换主题并写入 settings.toml
+#[tauri::command]
+pub async fn set_theme(app: AppHandle, theme: ThemePreference) -> CommandResult<()> {
+    blocking(move || {
+        crate::config::update(&app, |settings| settings.theme = theme)?;
+        apply(&app, theme);
+        log::info!("Theme set to {:?}", theme);
+        Ok(())
+    })
+    .await
+}
+
+#[tauri::command]
+pub fn get_theme(state: State<SettingsState>) -> ThemePreference {
+    state.get().theme
+}
+
+/// 跟随系统时，系统主题变化推给前端（`theme-changed` 事件），并重新应用到所有窗口
+pub fn track(window: &Window, event: &WindowEvent) {
+    let WindowEvent::ThemeChanged(theme) = event else {
+        return;
+    };
+    if window.label() != "main" {
+        return;
+    }
+    let app = window.app_handle();
+    if app.state::<SettingsState>().get().theme != ThemePreference::System {
+        return;
+    }
+    let theme = match theme {
+        Theme::Dark => "dark",
+        _ => "light",
+    };
+    log::info!("System theme changed to {}", theme);
+    apply(app, ThemePreference::System);
+    let _ = app.emit("theme-changed", ThemeChanged { theme });
+}
@@ -0,0 +1,93 @@
+use log::error;
+use tauri::menu::{Menu, MenuItem};
+use tauri::tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent};
+use tauri::{AppHandle, Manager, Wry};
+
+use crate::i18n::{tr, Msg};
+use crate::sidecar;
+
+const TRAY_ID: &str = "main";
+
+/// 创建托盘图标：显示窗口 / 重启后端 / 退出；无界面模式下没有窗口可显示
+pub fn init(app: &AppHandle, headless: bool) -> tauri::Result<()> {
+    let menu = menu(app, headless)?;
+
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+        .tooltip(tooltip_text(app, false))
+        .menu(&menu)
+        .show_menu_on_left_click(false)
+        .on_menu_event(|app, event| match event.id.as_ref() {
+            "show" => crate::show_main_window(app),
+            "restart" => restart_backend(app.clone()),
+            "quit" => crate::shutdown::quit(app, "tray quit"),
+            _ => {}
+        })
+        .on_tray_icon_event(|tray, event| {
+            if let TrayIconEvent::Click {
+                button: MouseButton::Left,
+                button_state: MouseButtonState::Up,
+                ..
+            } = event
+            {
+                crate::window::toggle_main(tray.app_handle());
+            }
+        });
+    if let Some(icon) = app.default_window_icon() {
+        builder = builder.icon(icon.clone());
+    }
+    builder.build(app)?;
+    Ok(())
+}
+
+/// 当前语言的托盘菜单
+fn menu(app: &AppHandle, headless: bool) -> tauri::Result<Menu<Wry>> {
+    let show = MenuItem::with_id(app, "show", tr(app, Msg::TrayShow), !headless, None::<&str>)?;
+    let restart = MenuItem::with_id(
+        app,
+        "restart",
+        tr(app, Msg::TrayRestart),
+        true,
+        None::<&str>,
+    )?;
+    let quit = MenuItem::with_id(app, "quit", tr(app, Msg::TrayQuit), true, None::<&str>)?;
+    Menu::with_items(app, &[&show, &restart, &quit])
+}
+
+fn tooltip_text(app: &AppHandle, running: bool) -> &'static str {
+    if running {
+        tr(app, Msg::TrayRunning)
+    } else {
+        tr(app, Msg::TrayStopped)
+    }
+}
+
+/// sidecar 启停后刷新托盘提示；调用方可能持有 child 锁，所以由调用方传入状态
+pub fn refresh(app: &AppHandle, running: bool) {
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        let _ = tray.set_tooltip(Some(tooltip_text(app, running)));
+    }
+}
+
+/// 切换语言后按新语言重建菜单和提示
+pub fn relocalize(app: &AppHandle) {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return;
+    };
+    match menu(app, crate::headless_requested()) {
+        Ok(menu) => {
+            let _ = tray.set_menu(Some(menu));
+        }
+        Err(e) => error!("Failed to rebuild the tray menu: {}", e),
+    }
+    let running = sidecar::current_pid(app).is_some();
+    let _ = tray.set_tooltip(Some(tooltip_text(app, running)));
+}
+
+fn restart_backend(app: AppHandle) {
+    tauri::async_runtime::spawn_blocking(move || {
+        if let Err(e) = sidecar::restart_by_user(&app) {
+            error!("Restart from tray failed: {}", e);
+            refresh(&app, false);
+        }
+    });
+}
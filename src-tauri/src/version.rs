@@ -0,0 +1,82 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State};
+use tauri_plugin_dialog::{DialogExt, MessageDialogKind};
+
+use crate::i18n::{self, tr, Msg};
+use crate::sidecar::lock_recover;
+use crate::RPC_TIMEOUT;
+
+/// 外壳自身的版本，sidecar 的主版本号需与之一致
+pub(crate) const SHELL_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// sidecar 通过 `version` RPC 报告的版本信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SidecarVersion {
+    pub version: String,
+    pub git_sha: String,
+}
+
+#[derive(Default)]
+pub struct VersionState {
+    reported: Mutex<Option<SidecarVersion>>,
+    /// 版本不兼容的提示每次运行只弹一次
+    warned: AtomicBool,
+}
+
+#[tauri::command]
+pub fn sidecar_version(state: State<VersionState>) -> Option<SidecarVersion> {
+    lock_recover(&state.reported, "Sidecar version").clone()
+}
+
+/// sidecar 就绪后询问其版本，主版本号不一致时提示重新安装
+pub fn handshake(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let reply = crate::rpc::call(&app, "version", serde_json::json!({}), RPC_TIMEOUT).await;
+        let version = match reply.map(serde_json::from_value::<SidecarVersion>) {
+            Ok(Ok(version)) => version,
+            Ok(Err(e)) => {
+                log::warn!("Sidecar sent an unexpected version reply: {}", e);
+                return;
+            }
+            Err(e) => {
+                log::warn!("Sidecar version handshake failed: {}", e);
+                return;
+            }
+        };
+        log::info!(
+            "Sidecar version {} ({}), shell version {}",
+            version.version,
+            version.git_sha,
+            SHELL_VERSION
+        );
+
+        let state = app.state::<VersionState>();
+        *lock_recover(&state.reported, "Sidecar version") = Some(version.clone());
+        if major(&version.version) != major(SHELL_VERSION) {
+            log::warn!(
+                "Sidecar major version {} does not match shell {}",
+                version.version,
+                SHELL_VERSION
+            );
+            if !state.warned.swap(true, Ordering::SeqCst) {
+                app.dialog()
+                    .message(i18n::format(
+                        tr(&app, Msg::Incompatible),
+                        &[("backend", &version.version), ("shell", SHELL_VERSION)],
+                    ))
+                    .title("NullGravity")
+                    .kind(MessageDialogKind::Warning)
+                    .show(|_| {});
+            }
+        }
+    });
+}
+
+fn major(version: &str) -> &str {
+    let version = version.trim().trim_start_matches('v');
+    version.split('.').next().unwrap_or(version)
+}
@@ -0,0 +1,116 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State, Window, WindowEvent};
+
+use crate::error::CommandResult;
+use crate::shutdown::{QuitGuard, QuitState};
+
+/// 窗口标题统一的品牌前缀，前端只传当前上下文
+const APP_NAME: &str = "NullGravity";
+const TITLE_SEPARATOR: &str = " — ";
+/// 上下文部分最多保留的字符数，超出截断并加省略号
+const TITLE_MAX_CHARS: usize = 80;
+
+/// 点击关闭按钮时的行为
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CloseBehavior {
+    /// 关闭窗口并退出（同时结束 sidecar）
+    #[default]
+    Quit,
+    /// 隐藏到托盘，sidecar 继续运行
+    Tray,
+}
+
+/// 主窗口相关的运行时状态
+#[derive(Default)]
+pub struct WindowState {
+    close_behavior: Mutex<CloseBehavior>,
+    /// 启动后是否已经显示过主窗口
+    revealed: AtomicBool,
+}
+
+impl WindowState {
+    pub fn set_close_behavior(&self, mode: CloseBehavior) {
+        *self.close_behavior.lock().unwrap() = mode;
+        log::info!("Close behavior set to {:?}", mode);
+    }
+}
+
+#[tauri::command]
+pub fn set_close_behavior(state: State<WindowState>, mode: CloseBehavior) {
+    state.set_close_behavior(mode);
+}
+
+/// 设置为 "NullGravity — <title>"，title 为空时恢复为 "NullGravity"
+#[tauri::command]
+pub fn set_window_title(window: Window, title: String) -> CommandResult<()> {
+    window.set_title(&branded_title(&title))?;
+    Ok(())
+}
+
+fn branded_title(title: &str) -> String {
+    let clean: String = title
+        .chars()
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .collect();
+    let clean = clean.trim();
+    if clean.is_empty() {
+        return APP_NAME.to_string();
+    }
+    let mut context: String = clean.chars().take(TITLE_MAX_CHARS).collect();
+    if clean.chars().count() > TITLE_MAX_CHARS {
+        context.push('…');
+    }
+    format!("{}{}{}", APP_NAME, TITLE_SEPARATOR, context)
+}
+
+/// 启动阶段只自动显示一次主窗口（同时关掉启动画面），之后 sidecar 重启不再打扰用户
+pub fn reveal_once(app: &AppHandle) {
+    let state = app.state::<WindowState>();
+    if !state.revealed.swap(true, Ordering::SeqCst) {
+        crate::splash::close(app);
+        crate::show_main_window(app);
+        crate::startup::mark(app, "window");
+    }
+}
+
+/// 主窗口可见则隐藏，否则显示并聚焦
+pub fn toggle_main(app: &AppHandle) {
+    let Some(window) = app.get_webview_window("main") else {
+        return;
+    };
+    if window.is_visible().unwrap_or(false) {
+        let _ = window.hide();
+    } else {
+        crate::show_main_window(app);
+    }
+}
+
+/// 托盘模式下拦截关闭，只隐藏窗口
+pub fn handle_close_requested(window: &Window, event: &WindowEvent) {
+    let WindowEvent::CloseRequested { api, .. } = event else {
+        return;
+    };
+    if window.label() != "main" {
+        return;
+    }
+    let behavior = *window
+        .app_handle()
+        .state::<WindowState>()
+        .close_behavior
+        .lock()
+        .unwrap();
+    if behavior == CloseBehavior::Tray {
+        api.prevent_close();
+        let _ = window.hide();
+        return;
+    }
+    let app = window.app_handle();
+    if app.state::<QuitGuard>().is_enabled() && !app.state::<QuitState>().is_quitting() {
+        api.prevent_close();
+        crate::shutdown::request_quit(app);
+    }
+}